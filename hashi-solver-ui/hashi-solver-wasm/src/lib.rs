@@ -1,6 +1,14 @@
+use std::cell::RefCell;
+use std::collections::BTreeMap;
 use std::io::Write;
 
-use hashi_solver::{Board, SolveState};
+use hashi_solver::analyze::Infeasibility;
+use hashi_solver::cache::SolverCache;
+use hashi_solver::{formats, techniques};
+use hashi_solver::{
+    solve_str, Board, Move, ParseError, Solution, SolveEvent, SolveOptions, SolveState, Step,
+};
+use serde::{Deserialize, Serialize};
 
 mod utils;
 
@@ -17,31 +25,650 @@ extern "C" {
     fn alert(s: &str);
 }
 
-fn _solve(s: &str, depth: usize, max_visited: usize) -> Result<String, &'static str> {
-    let b = Board::parse(s)?;
-    let (soln, log) = SolveState::new(&b).solve(depth, max_visited)?;
+const MAX_VISITED: usize = 10_000;
+const MAX_CACHED_BOARDS: usize = 16;
+
+thread_local! {
+    static CACHE: RefCell<SolverCache> = RefCell::new(SolverCache::new());
+}
+
+#[wasm_bindgen]
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum ErrorCode {
+    Parse,
+    InvalidInput,
+    IllegalBridge,
+    Unsolvable,
+    LimitExceeded,
+}
+
+// Thrown to JS by every export instead of returning the error text as a result.
+#[wasm_bindgen]
+#[derive(Debug, Clone)]
+pub struct HashiError {
+    code: ErrorCode,
+    message: String,
+    position: Option<(usize, usize)>,
+}
+
+#[wasm_bindgen]
+impl HashiError {
+    #[wasm_bindgen(getter)]
+    pub fn code(&self) -> ErrorCode {
+        self.code
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn message(&self) -> String {
+        self.message.clone()
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn x(&self) -> Option<usize> {
+        self.position.map(|(x, _)| x)
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn y(&self) -> Option<usize> {
+        self.position.map(|(_, y)| y)
+    }
+}
+
+impl HashiError {
+    fn new(code: ErrorCode, message: &str, position: Option<(usize, usize)>) -> Self {
+        HashiError {
+            code,
+            message: message.to_string(),
+            position,
+        }
+    }
+
+    fn solver(message: &'static str) -> Self {
+        let code = match message {
+            "max depth exceeded" | "max visited state count exceeded" => ErrorCode::LimitExceeded,
+            _ => ErrorCode::Unsolvable,
+        };
+        Self::new(code, message, None)
+    }
+}
+
+impl From<ParseError> for HashiError {
+    fn from(e: ParseError) -> Self {
+        Self::new(ErrorCode::Parse, e.message, Some(e.pos))
+    }
+}
+
+impl From<hashi_solver::HashiError> for HashiError {
+    fn from(e: hashi_solver::HashiError) -> Self {
+        match e {
+            hashi_solver::HashiError::Parse(e) => e.into(),
+            hashi_solver::HashiError::Solver(message) => Self::solver(message),
+        }
+    }
+}
+
+// A bridge placed by the player, as exchanged with the frontend.
+#[derive(Debug, Serialize, Deserialize)]
+struct Bridge {
+    from: (usize, usize),
+    to: (usize, usize),
+    #[serde(default = "one")]
+    count: u8,
+}
+
+fn one() -> u8 {
+    1
+}
+
+impl From<Bridge> for Move {
+    fn from(b: Bridge) -> Self {
+        Move {
+            from: b.from,
+            to: b.to,
+            count: b.count,
+        }
+    }
+}
+
+impl From<Move> for Bridge {
+    fn from(mv: Move) -> Self {
+        Bridge {
+            from: mv.from,
+            to: mv.to,
+            count: mv.count,
+        }
+    }
+}
+
+#[derive(Debug, Serialize)]
+struct Annotation {
+    from: (usize, usize),
+    to: (usize, usize),
+    reason: &'static str,
+}
+
+impl Annotation {
+    fn new(mv: Move, reason: &'static str) -> Self {
+        Annotation {
+            from: mv.from,
+            to: mv.to,
+            reason,
+        }
+    }
+}
+
+fn _solve(s: &str, depth: usize, max_visited: usize, concise: bool) -> Result<String, HashiError> {
+    let options = SolveOptions {
+        max_depth: depth,
+        max_visited,
+        ..Default::default()
+    };
+    let report = solve_str(s, &options)?;
     let mut results = vec![];
 
-    for i in 0..soln.len() {
+    let mut steps = report.steps;
+    if concise {
+        steps = Step::merge_repeats(&steps);
+    }
+    let mut placed = 0;
+    for (i, step) in steps.iter().enumerate() {
+        placed += step.mv.count as usize;
         writeln!(&mut results).unwrap();
         writeln!(&mut results, "Step {}", i + 1).unwrap();
-        writeln!(&mut results, "{}", log[i]).unwrap();
+        writeln!(&mut results, "{}", step).unwrap();
         writeln!(&mut results).unwrap();
-        write!(
-            &mut results,
-            "{}",
-            b.serialize_to_string(soln.iter().copied().take(i + 1))
-        )
-        .unwrap();
+        let soln = report.solution.edges.iter().copied().take(placed);
+        write!(&mut results, "{}", report.board.serialize_to_string(soln)).unwrap();
     }
 
     Ok(String::from_utf8_lossy(&results).to_string())
 }
 
+#[derive(Debug, Serialize)]
+struct Snapshots {
+    // The pairs of islands a bridge could join, which the counts in each snapshot are for.
+    edges: Vec<((usize, usize), (usize, usize))>,
+    snapshots: Vec<Vec<u8>>,
+}
+
+fn _solve_snapshots(s: &str, depth: usize, max_visited: usize) -> Result<String, HashiError> {
+    let b = Board::parse_any(s)?;
+    let (soln, log) = SolveState::new(&b)
+        .solve(depth, max_visited)
+        .map_err(HashiError::solver)?;
+    let (soln, _) = b.minimal_proof(&soln, &log);
+    let snapshots = b.edge_count_snapshots(&soln);
+    let edges = match snapshots.first() {
+        Some(first) => (0..first.len()).map(|e| b.edge_endpoints(e)).collect(),
+        None => vec![],
+    };
+    Ok(serde_json::to_string(&Snapshots { edges, snapshots }).unwrap())
+}
+
+#[derive(Debug, Serialize)]
+#[serde(tag = "kind", rename_all = "lowercase")]
+enum TraceEvent {
+    Added {
+        from: (usize, usize),
+        to: (usize, usize),
+        reason: &'static str,
+    },
+    Removed {
+        from: (usize, usize),
+        to: (usize, usize),
+    },
+    Solved,
+}
+
+fn _solve_trace(s: &str, depth: usize, max_visited: usize) -> Result<String, HashiError> {
+    let b = Board::parse_any(s)?;
+    let mut state = SolveState::new(&b).with_search_trace();
+    // A failed search is traced as well; it just doesn't end in `solved`.
+    let _ = state.solve(depth, max_visited);
+    let events = state
+        .search_trace()
+        .unwrap()
+        .iter()
+        .map(|event| match *event {
+            SolveEvent::EdgeAdded { edge, reason } => {
+                let (from, to) = b.edge_endpoints(edge);
+                TraceEvent::Added { from, to, reason }
+            }
+            SolveEvent::EdgeRemoved { edge } => {
+                let (from, to) = b.edge_endpoints(edge);
+                TraceEvent::Removed { from, to }
+            }
+            SolveEvent::Solved => TraceEvent::Solved,
+        })
+        .collect::<Vec<_>>();
+    Ok(serde_json::to_string(&events).unwrap())
+}
+
+// Convert a JSON list of bridges into moves, checking that each one connects a pair of islands.
+fn _parse_bridges(b: &Board, bridges: &str) -> Result<Vec<Move>, HashiError> {
+    let bridges: Vec<Bridge> = serde_json::from_str(bridges).map_err(|e| {
+        HashiError::new(
+            ErrorCode::InvalidInput,
+            &format!("could not parse bridge list: {}", e),
+            None,
+        )
+    })?;
+
+    let moves = bridges.into_iter().map(Move::from).collect::<Vec<_>>();
+    if let Some(mv) = moves.iter().find(|mv| b.edge_for_move(**mv).is_none()) {
+        return Err(HashiError::new(
+            ErrorCode::IllegalBridge,
+            "no bridge can connect these islands",
+            Some(mv.from),
+        ));
+    }
+    Ok(moves)
+}
+
+// Place the player's bridges on the board. Bridges which can't legally be placed are skipped and
+// returned alongside the resulting state.
+fn _apply_partial<'b>(
+    b: &'b Board,
+    partial: &str,
+) -> Result<(SolveState<'b>, Vec<Annotation>), HashiError> {
+    let mut state = SolveState::new(b);
+    let mut illegal = vec![];
+    for mv in _parse_bridges(b, partial)? {
+        for _ in 0..mv.count {
+            let single = Move { count: 1, ..mv };
+            if let Err(e) = state.add_move(single, "user") {
+                illegal.push(Annotation::new(single, e));
+            }
+        }
+    }
+
+    Ok((state, illegal))
+}
+
+fn _hint(s: &str, partial: &str, depth: usize, max_visited: usize) -> Result<String, HashiError> {
+    let b = Board::parse_any(s)?;
+    let (state, illegal) = _apply_partial(&b, partial)?;
+    if let Some(a) = illegal.first() {
+        return Err(HashiError::new(
+            ErrorCode::IllegalBridge,
+            a.reason,
+            Some(a.from),
+        ));
+    }
+
+    // The UI asks for a hint after every move, so the solution of the board is kept around
+    // between calls.
+    let options = SolveOptions {
+        max_depth: depth,
+        max_visited,
+        ..Default::default()
+    };
+    let (edge, reason) = CACHE
+        .with(|cache| {
+            let mut cache = cache.borrow_mut();
+            if cache.len() > MAX_CACHED_BOARDS {
+                cache.clear();
+            }
+            cache.hint(&state, &options)
+        })
+        .map_err(HashiError::solver)?;
+    let (from, to) = b.edge_endpoints(edge);
+    let mv = Move { from, to, count: 1 };
+    Ok(serde_json::to_string(&Annotation::new(mv, reason)).unwrap())
+}
+
+fn _validate(
+    s: &str,
+    partial: &str,
+    depth: usize,
+    max_visited: usize,
+) -> Result<String, HashiError> {
+    let b = Board::parse_any(s)?;
+    let (state, mut mistakes) = _apply_partial(&b, partial)?;
+
+    for mv in state
+        .mistake_moves(depth, max_visited)
+        .map_err(HashiError::solver)?
+    {
+        mistakes.push(Annotation::new(mv, "not part of the solution"));
+    }
+
+    Ok(serde_json::to_string(&mistakes).unwrap())
+}
+
+fn _visible_neighbors(s: &str, x: usize, y: usize) -> Result<String, HashiError> {
+    let b = Board::parse_any(s)?;
+    Ok(serde_json::to_string(&b.visible_neighbors((x, y))).unwrap())
+}
+
+// A problem with a board's clues, as reported by `sanity_check`.
+#[derive(Debug, Serialize)]
+struct Problem {
+    message: String,
+    // The island it's about, if it's about one.
+    pos: Option<(usize, usize)>,
+}
+
+fn _sanity_check(s: &str) -> Result<String, HashiError> {
+    let b = Board::parse_any(s)?;
+    let problems = b
+        .sanity_check()
+        .into_iter()
+        .map(|p| Problem {
+            message: p.to_string(),
+            pos: match p {
+                Infeasibility::OverfullIsland(pos) | Infeasibility::UnreachableIsland(pos) => {
+                    Some(pos)
+                }
+                Infeasibility::OddClueSum => None,
+            },
+        })
+        .collect::<Vec<_>>();
+    Ok(serde_json::to_string(&problems).unwrap())
+}
+
+// A game in progress as saved by `export_progress`. It's kept short for local storage: bridges
+// are `[x1, y1, x2, y2, count]`, and the puzzle is a game ID where it can be written as one.
+#[derive(Debug, Serialize, Deserialize)]
+struct Progress {
+    // Bumped whenever the format changes.
+    v: u32,
+    puzzle: String,
+    bridges: Vec<(usize, usize, usize, usize, u8)>,
+    hints: usize,
+}
+
+const PROGRESS_VERSION: u32 = 1;
+
+// A game read back by `import_progress`, in the formats the other exports take.
+#[derive(Debug, Serialize)]
+struct Game {
+    puzzle: String,
+    partial: Vec<Bridge>,
+    hints: usize,
+}
+
+fn _export_progress(s: &str, partial: &str, hints: usize) -> Result<String, HashiError> {
+    let b = Board::parse_any(s)?;
+    let bridges = _parse_bridges(&b, partial)?
+        .into_iter()
+        .map(|mv| (mv.from.0, mv.from.1, mv.to.0, mv.to.1, mv.count))
+        .collect();
+    let progress = Progress {
+        v: PROGRESS_VERSION,
+        puzzle: formats::to_sgt(&b).unwrap_or_else(|_| s.to_string()),
+        bridges,
+        hints,
+    };
+    Ok(serde_json::to_string(&progress).unwrap())
+}
+
+fn _import_progress(json: &str) -> Result<String, HashiError> {
+    let progress: Progress = serde_json::from_str(json).map_err(|e| {
+        HashiError::new(
+            ErrorCode::InvalidInput,
+            &format!("could not parse saved game: {}", e),
+            None,
+        )
+    })?;
+    if progress.v != PROGRESS_VERSION {
+        return Err(HashiError::new(
+            ErrorCode::InvalidInput,
+            "saved game is from an unsupported version",
+            None,
+        ));
+    }
+    let b = Board::parse_any(&progress.puzzle)?;
+    let moves = progress
+        .bridges
+        .into_iter()
+        .map(|(x1, y1, x2, y2, count)| Move {
+            from: (x1, y1),
+            to: (x2, y2),
+            count,
+        })
+        .collect::<Vec<_>>();
+    if let Some(mv) = moves.iter().find(|mv| b.edge_for_move(**mv).is_none()) {
+        return Err(HashiError::new(
+            ErrorCode::IllegalBridge,
+            "no bridge can connect these islands",
+            Some(mv.from),
+        ));
+    }
+    let game = Game {
+        puzzle: progress.puzzle,
+        partial: moves.into_iter().map(Bridge::from).collect(),
+        hints: progress.hints,
+    };
+    Ok(serde_json::to_string(&game).unwrap())
+}
+
+// A walkthrough kept for stepping back and forth through, e.g. with a timeline slider. Frame 0
+// is the board before any bridges are placed, and frame `i` the board after step `i` of `solve`.
+// Each frame is drawn from the bridge counts stored for it, rather than by placing every bridge
+// before it again.
 #[wasm_bindgen]
-pub fn solve(s: &str, depth: usize) -> String {
-    match _solve(s, depth, 10_000) {
-        Ok(r) => r,
-        Err(e) => e.to_string(),
+pub struct Timeline {
+    board: Board,
+    snapshots: Vec<Vec<u8>>,
+}
+
+#[wasm_bindgen]
+impl Timeline {
+    #[wasm_bindgen(constructor)]
+    pub fn new(s: &str, depth: usize) -> Result<Timeline, HashiError> {
+        utils::set_panic_hook();
+        let options = SolveOptions {
+            max_depth: depth,
+            max_visited: MAX_VISITED,
+            ..Default::default()
+        };
+        let report = solve_str(s, &options)?;
+        let mut snapshots = report.board.edge_count_snapshots(&report.solution.edges);
+        let empty = snapshots
+            .first()
+            .map_or(vec![], |first| vec![0; first.len()]);
+        snapshots.insert(0, empty);
+        Ok(Timeline {
+            board: report.board,
+            snapshots,
+        })
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn frames(&self) -> usize {
+        self.snapshots.len()
+    }
+
+    // The board at frame `i` as SVG.
+    pub fn get_frame(&self, i: usize) -> Result<String, HashiError> {
+        let counts = self.frame(i)?;
+        let edges = counts
+            .iter()
+            .enumerate()
+            .flat_map(|(edge, count)| std::iter::repeat_n(edge, *count as usize));
+        Ok(self.board.render_svg_to_string(edges))
+    }
+
+    // The bridges at frame `i`, as a JSON list in the same format as `hint`'s `partial`.
+    pub fn get_frame_bridges(&self, i: usize) -> Result<String, HashiError> {
+        let bridges = self
+            .frame(i)?
+            .iter()
+            .enumerate()
+            .filter(|(_, count)| **count > 0)
+            .map(|(edge, count)| {
+                let (from, to) = self.board.edge_endpoints(edge);
+                Bridge {
+                    from,
+                    to,
+                    count: *count,
+                }
+            })
+            .collect::<Vec<_>>();
+        Ok(serde_json::to_string(&bridges).unwrap())
+    }
+}
+
+impl Timeline {
+    fn frame(&self, i: usize) -> Result<&[u8], HashiError> {
+        self.snapshots
+            .get(i)
+            .map(|counts| &counts[..])
+            .ok_or_else(|| HashiError::new(ErrorCode::InvalidInput, "no such frame", None))
+    }
+}
+
+#[derive(Debug, Serialize)]
+struct Technique {
+    name: &'static str,
+    reason: &'static str,
+    preconditions: &'static str,
+    assumes_unique: bool,
+    example: String,
+    placed: Vec<Bridge>,
+    forced: Bridge,
+}
+
+fn _techniques() -> String {
+    let guide = techniques::describe_all()
+        .into_iter()
+        .map(|d| Technique {
+            name: d.name,
+            reason: d.reason,
+            preconditions: d.preconditions,
+            assumes_unique: d.assumes_unique,
+            example: d.example.to_puzzle_string(),
+            placed: d.placed.into_iter().map(Bridge::from).collect(),
+            forced: d.forced.into(),
+        })
+        .collect::<Vec<_>>();
+    serde_json::to_string(&guide).unwrap()
+}
+
+fn _render_svg(s: &str, soln: &str) -> Result<String, HashiError> {
+    let b = Board::parse_any(s)?;
+    let moves = _parse_bridges(&b, soln)?;
+
+    // Bridges may cross or leave islands short, as in a game in progress, but no pair of islands
+    // can take more than the variant allows.
+    let mut totals = BTreeMap::new();
+    for mv in &moves {
+        let total = totals.entry(b.edge_for_move(*mv)).or_insert(0);
+        *total += mv.count as usize;
+        if *total > b.variant().max_bridges as usize {
+            return Err(HashiError::new(
+                ErrorCode::IllegalBridge,
+                "too many bridges between a pair of islands",
+                Some(mv.from),
+            ));
+        }
     }
+
+    let soln = Solution::from_moves(&b, &moves)
+        .map_err(|e| HashiError::new(ErrorCode::IllegalBridge, e, None))?;
+    Ok(b.render_svg_to_string(soln.edges))
+}
+
+// A walkthrough of the solution for players, leaving out the search's dead ends and any guesses
+// it didn't need; see `solve_trace` for the search itself.
+#[wasm_bindgen]
+pub fn solve(s: &str, depth: usize) -> Result<String, HashiError> {
+    utils::set_panic_hook();
+    _solve(s, depth, MAX_VISITED, false)
+}
+
+// Like `solve`, but with bridges placed between the same islands for the same reason in
+// consecutive steps shown as a single step.
+#[wasm_bindgen]
+pub fn solve_concise(s: &str, depth: usize) -> Result<String, HashiError> {
+    utils::set_panic_hook();
+    _solve(s, depth, MAX_VISITED, true)
+}
+
+// The bridges on the board after each step of `solve`, as `{"edges": [[[x, y], [x, y]], ...],
+// "snapshots": [[n, ...], ...]}` with a count for each pair of islands in `edges`, so that the UI
+// can jump to any step without replaying the ones before it.
+#[wasm_bindgen]
+pub fn solve_snapshots(s: &str, depth: usize) -> Result<String, HashiError> {
+    utils::set_panic_hook();
+    _solve_snapshots(s, depth, MAX_VISITED)
+}
+
+// The search as it ran, for debugging the solver rather than showing to players: a JSON list of
+// `{"kind": "added", "from": [x, y], "to": [x, y], "reason": "..."}`, `{"kind": "removed", ...}`
+// for each single bridge placed or taken back, ending in `{"kind": "solved"}` if it was solved.
+#[wasm_bindgen]
+pub fn solve_trace(s: &str, depth: usize) -> Result<String, HashiError> {
+    utils::set_panic_hook();
+    _solve_trace(s, depth, MAX_VISITED)
+}
+
+// `partial` is a JSON list of `{"from": [x, y], "to": [x, y], "count": n}` bridges. Returns the
+// next bridge to place as `{"from": [x, y], "to": [x, y], "reason": "..."}`.
+#[wasm_bindgen]
+pub fn hint(s: &str, partial: &str, depth: usize) -> Result<String, HashiError> {
+    utils::set_panic_hook();
+    _hint(s, partial, depth, MAX_VISITED)
+}
+
+// Returns a JSON list of the bridges in `partial` which are illegal or not part of the solution,
+// in the same format as `hint`.
+#[wasm_bindgen]
+pub fn validate(s: &str, partial: &str, depth: usize) -> Result<String, HashiError> {
+    utils::set_panic_hook();
+    _validate(s, partial, depth, MAX_VISITED)
+}
+
+// `soln` is a JSON list of bridges in the same format as the `partial` argument to `hint`.
+#[wasm_bindgen]
+pub fn render_svg(s: &str, soln: &str) -> Result<String, HashiError> {
+    utils::set_panic_hook();
+    _render_svg(s, soln)
+}
+
+// Returns a JSON list of the `[x, y]` positions of the islands the island at (x, y) could have
+// bridges to, e.g. to highlight them when it's tapped.
+#[wasm_bindgen]
+pub fn visible_neighbors(s: &str, x: usize, y: usize) -> Result<String, HashiError> {
+    utils::set_panic_hook();
+    _visible_neighbors(s, x, y)
+}
+
+// Returns a JSON list of `{"message": "...", "pos": [x, y]}` for each problem with the clues which
+// makes the board impossible, without solving it, so it's quick enough to run as the puzzle is
+// typed in. `pos` is the island the problem is with, or null for the board as a whole.
+#[wasm_bindgen]
+pub fn sanity_check(s: &str) -> Result<String, HashiError> {
+    utils::set_panic_hook();
+    _sanity_check(s)
+}
+
+// The techniques the solver can use beyond each island's own clue, for an in-app strategy guide:
+// a JSON list of `{"name": "...", "reason": "...", "preconditions": "...", "assumes_unique": false,
+// "example": "...", "placed": [...], "forced": {...}}`, where `example` is a board in the format
+// `solve` takes, and `placed` and `forced` are bridges in the same format as `hint`'s `partial`.
+// Once the bridges in `placed` are on the board, the technique places `forced`, giving `reason`.
+#[wasm_bindgen]
+pub fn techniques() -> String {
+    utils::set_panic_hook();
+    _techniques()
+}
+
+// Save a game in progress, e.g. to local storage: the puzzle, the player's bridges as a JSON list
+// in the same format as `hint`'s `partial`, and how many hints they've had. Returns a short JSON
+// string for `import_progress` to read back.
+#[wasm_bindgen]
+pub fn export_progress(s: &str, partial: &str, hints: usize) -> Result<String, HashiError> {
+    utils::set_panic_hook();
+    _export_progress(s, partial, hints)
+}
+
+// Read back a game saved by `export_progress`, as `{"puzzle": "...", "partial": [...], "hints":
+// n}`, where `puzzle` can be passed to the other exports and `partial` is in the same format as
+// `hint`'s.
+#[wasm_bindgen]
+pub fn import_progress(json: &str) -> Result<String, HashiError> {
+    utils::set_panic_hook();
+    _import_progress(json)
 }
@@ -243,7 +243,15 @@ class TestPage extends React.Component {
     }
     this.setState({ ...this.state, solving: true });
 
-    const soln = solve(this.state.text, 3);
+    let soln;
+    try {
+      soln = solve(this.state.text, 3);
+    } catch (e) {
+      soln =
+        e.x !== undefined
+          ? `${e.message} at (${e.x}, ${e.y})`
+          : e.message;
+    }
 
     this.setState({ ...this.state, soln, solving: false });
   }
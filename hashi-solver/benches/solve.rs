@@ -0,0 +1,61 @@
+//! Solver benchmarks over the boards in the `puzzles` module, grouped by size, along with a few
+//! adversarial boards which push the search to its limits. Run with
+//! `cargo bench --features benches`.
+
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion};
+use hashi_solver::puzzles::{self, Difficulty};
+use hashi_solver::{Board, SolveOptions, SolveState};
+
+// Boards the solver gives up on, which is where the search spends the most time.
+const ADVERSARIAL: &[(&str, &str)] = &[
+    // The clues add up to an odd number, so every branch ends in a dead end.
+    ("odd_clue_sum", "\n4 5 4\n\n2 1 1\n"),
+    // Any spanning tree would do, so there's nothing to deduce and every edge is a candidate.
+    ("wildcards_7x5", "? ? ? ?\n\n? ? ? ?\n\n? ? ? ?"),
+];
+
+fn solve(board: &Board) -> bool {
+    SolveState::new(board)
+        .solve_with(&SolveOptions::default())
+        .is_ok()
+}
+
+fn bench_puzzles(c: &mut Criterion) {
+    for (name, difficulty) in [
+        ("small", Difficulty::Easy),
+        ("medium", Difficulty::Medium),
+        ("large", Difficulty::Hard),
+    ] {
+        let mut group = c.benchmark_group(name);
+        for puzzle in puzzles::by_difficulty(difficulty) {
+            let board = puzzle.board();
+            group.bench_with_input(
+                BenchmarkId::new("solve", puzzle.name),
+                &board,
+                |b, board| b.iter(|| assert!(solve(board))),
+            );
+            // Parsing includes finding every edge and crossing, which the solver relies on.
+            group.bench_with_input(
+                BenchmarkId::new("parse", puzzle.name),
+                puzzle.puzzle,
+                |b, s| b.iter(|| Board::parse(s).unwrap()),
+            );
+        }
+        group.finish();
+    }
+}
+
+fn bench_adversarial(c: &mut Criterion) {
+    let mut group = c.benchmark_group("adversarial");
+    group.sample_size(10);
+    for (name, s) in ADVERSARIAL {
+        let board = Board::parse(s).unwrap();
+        group.bench_with_input(BenchmarkId::new("solve", name), &board, |b, board| {
+            b.iter(|| solve(board))
+        });
+    }
+    group.finish();
+}
+
+criterion_group!(benches, bench_puzzles, bench_adversarial);
+criterion_main!(benches);
@@ -0,0 +1,40 @@
+#ifndef HASHI_SOLVER_H
+#define HASHI_SOLVER_H
+
+/*
+ * C interface to hashi-solver. Build the library with e.g.
+ *
+ *     cargo rustc --release --features ffi --crate-type cdylib
+ *
+ * (or `--crate-type staticlib`) and link against the result.
+ *
+ * Functions which can fail return NULL and, if `err` is non-NULL, store a message in `*err`. All
+ * strings returned by the library must be released with `hashi_string_free`. A panic inside the
+ * solver is reported the same way rather than unwinding into the caller.
+ */
+
+#include <stddef.h>
+
+#ifdef __cplusplus
+extern "C" {
+#endif
+
+typedef struct HashiBoard HashiBoard;
+
+/* Parse a puzzle, one row per line with digits for islands and spaces for water. */
+HashiBoard *hashi_parse(const char *puzzle, char **err);
+
+/* Solve a board, returning the solution rendered as text. */
+char *hashi_solve(const HashiBoard *board, size_t max_depth, size_t max_visited, char **err);
+
+/* Release a board returned by `hashi_parse`. */
+void hashi_free(HashiBoard *board);
+
+/* Release a string returned by the library. */
+void hashi_string_free(char *s);
+
+#ifdef __cplusplus
+}
+#endif
+
+#endif /* HASHI_SOLVER_H */
@@ -0,0 +1,670 @@
+//! Quick estimates about a board, for when running the full solver would be too slow or would
+//! only give a single answer.
+
+use alloc::vec;
+use alloc::vec::Vec;
+use core::fmt;
+use core::time::Duration;
+
+use crate::rng::Rng;
+use crate::techniques::{self, Techniques};
+use crate::{Board, ClueKind, Contradiction, Move, SolveOptions, SolveOutcome, SolveState, Step};
+
+// Cheap signs of how hard a board will be, for filtering candidate boards before rating them
+// properly.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct HardnessEstimate {
+    // The fraction of cells holding an island.
+    pub island_density: f64,
+    // The average number of islands each island could have bridges to.
+    pub average_degree: f64,
+    // The fraction of the board's bridges placed by deduction alone, before any speculation.
+    pub forced_fraction: f64,
+}
+
+impl HardnessEstimate {
+    // Higher is harder; 0 means the board is solved by deduction alone. Each bridge left to
+    // speculate on is weighted by how many ways there are to place it.
+    pub fn score(&self) -> f64 {
+        (1.0 - self.forced_fraction) * self.average_degree
+    }
+}
+
+pub fn estimate_hardness(board: &Board) -> HardnessEstimate {
+    let (width, height) = board.size();
+    let islands = board.nodes.len();
+
+    let mut state = SolveState::new(board);
+    let mut forced = 0;
+    while let Some((edge, reason)) = state.solve_fully_constrained() {
+        state.add_edge(edge, reason);
+        forced += 1;
+    }
+    // Wildcard and open-ended clues count for the fewest bridges they allow.
+    let bridges = board
+        .nodes
+        .iter()
+        .map(|n| n.min_bridges() as usize)
+        .sum::<usize>()
+        / 2;
+
+    HardnessEstimate {
+        island_density: ratio(islands, width * height),
+        average_degree: ratio(2 * board.edges.len(), islands),
+        forced_fraction: if bridges == 0 {
+            1.0
+        } else {
+            ratio(forced, bridges).min(1.0)
+        },
+    }
+}
+
+// What solving a board took, for printing alongside its rating, e.g. "requires: bottleneck, 1
+// guess".
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DifficultyReport {
+    // Each technique used by the walkthrough, in the order they first appear.
+    pub techniques: Vec<TechniqueUse>,
+    // Bridges in the walkthrough which couldn't be deduced.
+    pub guesses: usize,
+    // The most guesses the search had to make on top of one another.
+    pub deepest_guess: usize,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TechniqueUse {
+    pub name: &'static str,
+    // How many bridges it placed.
+    pub count: usize,
+    // The index into `Board::walkthrough` of the first of them.
+    pub first_step: usize,
+}
+
+impl fmt::Display for DifficultyReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("requires: ")?;
+        for (i, t) in self.techniques.iter().enumerate() {
+            if i > 0 {
+                f.write_str(", ")?;
+            }
+            for c in t.name.chars() {
+                write!(f, "{}", c.to_ascii_lowercase())?;
+            }
+        }
+        match self.guesses {
+            0 => Ok(()),
+            1 => write!(f, ", 1 guess"),
+            n => write!(f, ", {} guesses", n),
+        }
+    }
+}
+
+// Solve the board with every technique which doesn't assume a single solution, and report which
+// of them the walkthrough needed and how much guessing was left.
+pub fn difficulty_report(
+    board: &Board,
+    options: &SolveOptions,
+) -> Result<DifficultyReport, &'static str> {
+    let mut state = SolveState::new(board).with_techniques(Techniques::all());
+    let (soln, log) = state.solve_with(options)?;
+    let steps = board.walkthrough(&soln, &log);
+
+    let mut used: Vec<TechniqueUse> = vec![];
+    for (i, step) in steps.iter().enumerate() {
+        let Some(name) = techniques::name(step.reason) else {
+            continue;
+        };
+        match used.iter_mut().find(|t| t.name == name) {
+            Some(t) => t.count += 1,
+            None => used.push(TechniqueUse {
+                name,
+                count: 1,
+                first_step: i,
+            }),
+        }
+    }
+    Ok(DifficultyReport {
+        techniques: used,
+        guesses: steps.iter().filter(|s| s.reason == "speculative").count(),
+        deepest_guess: state.stats().deepest_guess,
+    })
+}
+
+// The index of the earliest of the player's moves, in the order they made them, after which the
+// board can no longer be solved, e.g. for undoing back to where they went wrong. A move which
+// can't be placed at all counts too. `None` if the board can still be solved after all of them.
+// Prefixes of the moves are solved from scratch, binary searching for the shortest one which
+// can't be completed, so this takes a few solves rather than one per move. Fails if a solve gives
+// up within `options`' limits, without having ruled anything out.
+pub fn first_mistake(
+    board: &Board,
+    moves: &[Move],
+    options: &SolveOptions,
+) -> Result<Option<usize>, &'static str> {
+    let solvable = |prefix: &[Move]| -> Result<bool, &'static str> {
+        let Ok(mut state) = SolveState::new(board).with_locked_moves(prefix) else {
+            return Ok(false);
+        };
+        match state.solve_outcome(options) {
+            SolveOutcome::Solved { .. } => Ok(true),
+            SolveOutcome::ProvedUnsolvable(_) => Ok(false),
+            SolveOutcome::Inconclusive(limit) => Err(limit),
+        }
+    };
+    if solvable(moves)? {
+        return Ok(None);
+    }
+    // The first `lo` moves can be completed, and the first `hi` can't.
+    let (mut lo, mut hi) = (0, moves.len());
+    if !solvable(&[])? {
+        return Err("board has no solution");
+    }
+    while hi - lo > 1 {
+        let mid = lo + (hi - lo) / 2;
+        if solvable(&moves[..mid])? {
+            lo = mid;
+        } else {
+            hi = mid;
+        }
+    }
+    Ok(Some(hi - 1))
+}
+
+// How well a player solved a board, as worked out by `score`.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct Score {
+    // Out of `MAX_SCORE`.
+    pub points: u32,
+    // The fewest moves the board can be solved in: one per pair of islands joined in the solution.
+    pub par: usize,
+    pub moves: usize,
+    // Moves which put more bridges between a pair of islands than the solution has.
+    pub mistakes: usize,
+    pub hints: usize,
+}
+
+pub const MAX_SCORE: u32 = 1000;
+const MISTAKE_PENALTY: u32 = 100;
+const HINT_PENALTY: u32 = 50;
+const EXTRA_MOVE_PENALTY: u32 = 10;
+// The time allowed per move of par before points are taken off, one per second over.
+const SECONDS_PER_MOVE: u64 = 5;
+
+// Score the player's moves, in the order they made them, having asked for `hints` hints and taken
+// `time`, so that every frontend scores the same way. Mistakes are counted against the solution the
+// solver finds, so on a board with several solutions, a different one can cost points.
+pub fn score(
+    board: &Board,
+    moves: &[Move],
+    hints: usize,
+    time: Duration,
+    options: &SolveOptions,
+) -> Result<Score, &'static str> {
+    let (soln, _) = SolveState::new(board).solve_with(options)?;
+    let mut remaining = vec![0u8; board.edges.len()];
+    for edge in soln {
+        remaining[edge] += 1;
+    }
+    let par = remaining.iter().filter(|n| **n > 0).count();
+
+    let mut mistakes = 0;
+    for mv in moves {
+        match board.edge_for_move(*mv) {
+            Some(edge) if remaining[edge] >= mv.count => remaining[edge] -= mv.count,
+            _ => mistakes += 1,
+        }
+    }
+
+    let allowed = Duration::from_secs(SECONDS_PER_MOVE * par as u64);
+    let clamp = |n: u64| u32::try_from(n).unwrap_or(u32::MAX);
+    let extra_moves = moves.len().saturating_sub(par);
+    let penalty = MISTAKE_PENALTY
+        .saturating_mul(clamp(mistakes as u64))
+        .saturating_add(HINT_PENALTY.saturating_mul(clamp(hints as u64)))
+        .saturating_add(EXTRA_MOVE_PENALTY.saturating_mul(clamp(extra_moves as u64)))
+        .saturating_add(clamp(time.saturating_sub(allowed).as_secs()));
+    Ok(Score {
+        points: MAX_SCORE.saturating_sub(penalty),
+        par,
+        moves: moves.len(),
+        mistakes,
+        hints,
+    })
+}
+
+// For each edge of the board, an estimate of how likely it is to have a bridge in a solution,
+// e.g. for shading candidate bridges in a heatmap. Each sample makes deductions as far as they go,
+// then places a bridge on a random candidate edge, and so on until the board is either solved or
+// found to be stuck. Only the samples that solve the board count, so every probability is zero if
+// none of them did. Solutions which are easier to stumble upon are favored, so this is a hint,
+// not an exact count; use `solver::brute_force` for that.
+pub fn edge_probabilities(board: &Board, samples: usize, seed: u64) -> Vec<f64> {
+    let mut rng = Rng(seed);
+    let mut used = vec![0; board.edges.len()];
+    let mut solved = 0;
+
+    for _ in 0..samples {
+        let mut state = SolveState::new(board);
+        loop {
+            while let Some((edge, reason)) = state.solve_fully_constrained() {
+                state.add_edge(edge, reason);
+            }
+            if state.solved() {
+                solved += 1;
+                for (count, used) in state.edge_counts.iter().zip(used.iter_mut()) {
+                    if count.count() > 0 {
+                        *used += 1;
+                    }
+                }
+                break;
+            }
+            let next = state.find_next_edges();
+            if state.solvable().is_err() || next.is_empty() {
+                break;
+            }
+            state.add_edge(next[rng.range(0, next.len() - 1)], "speculative");
+        }
+    }
+
+    used.into_iter().map(|n| ratio(n, solved)).collect()
+}
+
+// Why a board can't have any solution, found without searching.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Infeasibility {
+    // Every bridge counts towards two islands, so exact clues must add up to an even number.
+    OddClueSum,
+    // The island at this position needs more bridges than its neighbours have room for.
+    OverfullIsland((usize, usize)),
+    // The island at this position has no other island in reach to bridge to.
+    UnreachableIsland((usize, usize)),
+}
+
+impl Infeasibility {
+    pub fn reason(self) -> &'static str {
+        match self {
+            Infeasibility::OddClueSum => "clues add up to an odd number",
+            Infeasibility::OverfullIsland(_) => "island needs more bridges than it has room for",
+            Infeasibility::UnreachableIsland(_) => "island has no neighbours to bridge to",
+        }
+    }
+}
+
+impl fmt::Display for Infeasibility {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Infeasibility::OddClueSum => write!(
+                f,
+                "the clues add up to an odd number, but every bridge counts towards two islands"
+            ),
+            Infeasibility::OverfullIsland((x, y)) => write!(
+                f,
+                "the island at ({}, {}) needs more bridges than its neighbours have room for",
+                x, y
+            ),
+            Infeasibility::UnreachableIsland((x, y)) => {
+                write!(
+                    f,
+                    "the island at ({}, {}) has no neighbours to bridge to",
+                    x, y
+                )
+            }
+        }
+    }
+}
+
+// Cheap checks which rule out many impossible boards, e.g. from a generator, before spending a
+// search on them. `None` doesn't mean the board is solvable, only that these checks passed.
+pub fn quick_infeasibility(board: &Board) -> Option<Infeasibility> {
+    infeasibilities(board).into_iter().next()
+}
+
+// Every problem `quick_infeasibility` checks for, rather than just the first: each island's, in
+// order, then the clue total's.
+pub fn infeasibilities(board: &Board) -> Vec<Infeasibility> {
+    let mut problems = vec![];
+    for node in board.node_ids() {
+        let island = board.nodes[node.index()];
+        let degree = board.node_edges.get(node.index()).len();
+        if degree == 0 && board.nodes.len() > 1 {
+            problems.push(Infeasibility::UnreachableIsland(island.pos));
+        } else if island.min_bridges() as usize > degree * board.variant.max_bridges as usize {
+            problems.push(Infeasibility::OverfullIsland(island.pos));
+        }
+    }
+    if odd_clue_sum(board) {
+        problems.push(Infeasibility::OddClueSum);
+    }
+    problems
+}
+
+// Only exact clues pin down the total; wildcards and ranges could make up the difference.
+pub(crate) fn odd_clue_sum(board: &Board) -> bool {
+    let exact = board
+        .nodes
+        .iter()
+        .map(|n| n.n.filter(|_| n.kind == ClueKind::Exact))
+        .collect::<Option<Vec<_>>>();
+    exact.is_some_and(|clues| clues.iter().map(|n| *n as usize).sum::<usize>() % 2 == 1)
+}
+
+// Why a board has no solution, in a form that can be checked by hand.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Certificate {
+    // The board fails one of the checks of `quick_infeasibility`.
+    Infeasible(Infeasibility),
+    // The bridges which are forced from here lead to a contradiction.
+    Forced {
+        steps: Vec<Step>,
+        contradiction: Contradiction,
+    },
+    // After the forced bridges, the island at `island` still needs a bridge, but each bridge it
+    // could take leads to a contradiction in turn.
+    CaseSplit {
+        steps: Vec<Step>,
+        island: (usize, usize),
+        cases: Vec<(Move, Certificate)>,
+    },
+    // The search ruled out every option, but not in few enough case splits to write down. This
+    // is the reason the last option failed.
+    Exhausted(&'static str),
+}
+
+impl Certificate {
+    fn fmt_indented(&self, f: &mut fmt::Formatter<'_>, indent: usize) -> fmt::Result {
+        let steps = match self {
+            Certificate::Forced { steps, .. } | Certificate::CaseSplit { steps, .. } => &steps[..],
+            _ => &[],
+        };
+        for step in steps {
+            writeln!(f, "{:indent$}{}", "", step)?;
+        }
+
+        match self {
+            Certificate::Infeasible(infeasible) => writeln!(f, "{:indent$}{}", "", infeasible),
+            Certificate::Forced { contradiction, .. } if steps.is_empty() => {
+                writeln!(f, "{:indent$}{}", "", contradiction)
+            }
+            Certificate::Forced { contradiction, .. } => {
+                writeln!(f, "{:indent$}so {}", "", contradiction)
+            }
+            Certificate::CaseSplit {
+                island: (x, y),
+                cases,
+                ..
+            } => {
+                writeln!(
+                    f,
+                    "{:indent$}the island at ({}, {}) needs another bridge, but each one fails:",
+                    "", x, y
+                )?;
+                for (mv, certificate) in cases {
+                    writeln!(
+                        f,
+                        "{:indent$}- with a bridge to ({}, {}):",
+                        "", mv.to.0, mv.to.1
+                    )?;
+                    certificate.fmt_indented(f, indent + 2)?;
+                }
+                Ok(())
+            }
+            Certificate::Exhausted(reason) => {
+                writeln!(f, "{:indent$}every option fails ({})", "", reason)
+            }
+        }
+    }
+}
+
+// One line per step of the explanation, with each case of a split indented under it.
+impl fmt::Display for Certificate {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.fmt_indented(f, 0)
+    }
+}
+
+// Explain why the state can't be completed, splitting on at most `max_depth` islands in a row,
+// each time on the island with the fewest bridges to choose from. `None` if the state can be
+// completed, or if it takes more case splits than that to show it can't.
+pub fn explain_unsolvable(state: &SolveState, max_depth: usize) -> Option<Certificate> {
+    if let Some(infeasible) = quick_infeasibility(state.board) {
+        return Some(Certificate::Infeasible(infeasible));
+    }
+    // Start afresh from the same bridges, rather than cloning everything the search has recorded
+    // at every split.
+    let mut fresh = SolveState::new(state.board);
+    for (edge, reason) in state.soln.iter().zip(&state.log) {
+        fresh.add_edge(*edge, reason);
+    }
+    refute(fresh, max_depth)
+}
+
+fn refute(mut state: SolveState, depth: usize) -> Option<Certificate> {
+    let mut steps = vec![];
+    loop {
+        if let Err(contradiction) = state.solvable() {
+            return Some(Certificate::Forced {
+                steps: Step::merge_repeats(&steps),
+                contradiction,
+            });
+        }
+        let Some((node, edge, reason)) = state.find_forced_edge() else {
+            break;
+        };
+        let mv = state.move_from(state.board.nodes[node.index()].pos, edge, 1);
+        state.add_edge(edge.index(), reason);
+        steps.push(Step {
+            mv,
+            total: state.edge_counts[edge.index()].count(),
+            reason,
+        });
+    }
+    if depth == 0 || state.solved() {
+        return None;
+    }
+
+    // Any solution from here gives the island at least one more bridge, along one of these edges.
+    let island = state
+        .board
+        .node_ids()
+        .filter(|node| state.required(*node) > 0)
+        .min_by_key(|node| state.available_edges_for_node(*node).count())?;
+    let pos = state.board.nodes[island.index()].pos;
+    let mut cases = vec![];
+    for (edge, _) in state.available_edges_for_node(island).collect::<Vec<_>>() {
+        let mut case = state.clone();
+        case.add_edge(edge.index(), "case split");
+        cases.push((state.move_from(pos, edge, 1), refute(case, depth - 1)?));
+    }
+    Some(Certificate::CaseSplit {
+        steps: Step::merge_repeats(&steps),
+        island: pos,
+        cases,
+    })
+}
+
+fn ratio(a: usize, b: usize) -> f64 {
+    if b == 0 {
+        0.0
+    } else {
+        a as f64 / b as f64
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::SolveOptions;
+
+    #[test]
+    fn test_difficulty_report() {
+        let b = Board::parse("3 3 2\n\n1   1").unwrap();
+        let report = difficulty_report(&b, &SolveOptions::default()).unwrap();
+        assert_eq!((report.guesses, report.deepest_guess), (0, 0));
+        assert_eq!(report.techniques[0].first_step, 0);
+        // Every one of the five bridges is deduced.
+        let total = report.techniques.iter().map(|t| t.count).sum::<usize>();
+        assert_eq!(total, 5);
+        assert_eq!(
+            report.to_string(),
+            "requires: every neighbour, only neighbour"
+        );
+
+        // Two ways round the square, so one bridge has to be guessed.
+        let b = Board::parse("3 3\n\n3 3").unwrap();
+        let report = difficulty_report(&b, &SolveOptions::default()).unwrap();
+        assert_eq!((report.guesses, report.deepest_guess), (1, 1));
+        assert!(report.to_string().starts_with("requires: "));
+        assert!(report.to_string().ends_with(", 1 guess"));
+    }
+
+    #[test]
+    fn test_first_mistake() {
+        let b = Board::parse("3 3 2\n\n1   1").unwrap();
+        let mv = |from, to, count| Move { from, to, count };
+        let options = SolveOptions::default();
+        let correct = [mv((0, 0), (0, 2), 1), mv((0, 0), (2, 0), 2)];
+        assert_eq!(first_mistake(&b, &correct, &options), Ok(None));
+        assert_eq!(first_mistake(&b, &[], &options), Ok(None));
+
+        // The 2 is finished off without reaching the 1 below it.
+        let moves = [
+            mv((0, 0), (0, 2), 1),
+            mv((2, 0), (4, 0), 2),
+            mv((0, 0), (2, 0), 1),
+        ];
+        assert_eq!(first_mistake(&b, &moves, &options), Ok(Some(1)));
+        // Too many bridges for the 1 to take.
+        let moves = [mv((0, 0), (2, 0), 2), mv((0, 0), (0, 2), 2)];
+        assert_eq!(first_mistake(&b, &moves, &options), Ok(Some(1)));
+
+        let b = Board::parse("1 1\n\n1 1").unwrap();
+        assert_eq!(
+            first_mistake(&b, &[mv((0, 0), (2, 0), 1)], &options),
+            Err("board has no solution")
+        );
+    }
+
+    #[test]
+    fn test_score() {
+        let b = Board::parse("3 3 2\n\n1   1").unwrap();
+        let mv = |from, to, count| Move { from, to, count };
+        let options = SolveOptions::default();
+        let perfect = [
+            mv((0, 0), (2, 0), 2),
+            mv((0, 0), (0, 2), 1),
+            mv((2, 0), (4, 0), 1),
+            mv((4, 0), (4, 2), 1),
+        ];
+        let s = score(&b, &perfect, 0, Duration::from_secs(20), &options).unwrap();
+        assert_eq!((s.points, s.par, s.mistakes), (MAX_SCORE, 4, 0));
+
+        // A second bridge to the 1, one at a time across the top, a hint and a minute.
+        let sloppy = [
+            mv((0, 0), (2, 0), 1),
+            mv((0, 0), (2, 0), 1),
+            mv((4, 0), (4, 2), 2),
+            mv((0, 0), (0, 2), 1),
+            mv((2, 0), (4, 0), 1),
+            mv((4, 0), (4, 2), 1),
+        ];
+        let s = score(&b, &sloppy, 1, Duration::from_secs(60), &options).unwrap();
+        assert_eq!((s.moves, s.mistakes, s.hints), (6, 1, 1));
+        assert_eq!(s.points, MAX_SCORE - 100 - 50 - 2 * 10 - 40);
+
+        // Penalties too large for a u32 bottom out at zero rather than overflowing or wrapping.
+        let forever = Duration::from_secs(u32::MAX as u64);
+        let s = score(&b, &sloppy, 0, forever, &options).unwrap();
+        assert_eq!(s.points, 0);
+        let s = score(
+            &b,
+            &perfect,
+            (u32::MAX as usize).saturating_add(1),
+            Duration::ZERO,
+            &options,
+        )
+        .unwrap();
+        assert_eq!(s.points, 0);
+        let s = score(&b, &perfect, usize::MAX, Duration::MAX, &options).unwrap();
+        assert_eq!(s.points, 0);
+    }
+
+    #[test]
+    fn test_estimate_hardness() {
+        let b = Board::parse("3 3 2\n\n1   1").unwrap();
+        let estimate = estimate_hardness(&b);
+        assert_eq!(estimate.island_density, 5.0 / 15.0);
+        assert_eq!(estimate.average_degree, 2.0);
+        assert_eq!(estimate.forced_fraction, 1.0);
+        assert_eq!(estimate.score(), 0.0);
+
+        // A single bridge on each side is forced, but the other two could go either way.
+        let b = Board::parse("3 3\n\n3 3").unwrap();
+        let estimate = estimate_hardness(&b);
+        assert_eq!(estimate.forced_fraction, 4.0 / 6.0);
+        assert!(estimate.score() > 0.0);
+    }
+
+    #[test]
+    fn test_edge_probabilities() {
+        let b = Board::parse("3 3 2\n\n1   1").unwrap();
+        let (soln, _) = SolveState::new(&b).solve(0, 0).unwrap();
+        let probabilities = edge_probabilities(&b, 10, 0);
+        for (edge, p) in probabilities.iter().enumerate() {
+            assert_eq!(*p, if soln.contains(&edge) { 1.0 } else { 0.0 });
+        }
+
+        // Both solutions use every edge, with one or two bridges.
+        let b = Board::parse("3 3\n\n3 3").unwrap();
+        assert_eq!(edge_probabilities(&b, 10, 0), vec![1.0; 4]);
+        assert_eq!(edge_probabilities(&b, 0, 0), vec![0.0; 4]);
+    }
+
+    #[test]
+    fn test_quick_infeasibility() {
+        let check = |s| quick_infeasibility(&Board::parse(s).unwrap());
+        assert_eq!(check("3 3 2\n\n1   1"), None);
+        assert_eq!(check("4 5 4\n\n2 1 1"), Some(Infeasibility::OddClueSum));
+        // A range clue could take up the slack.
+        assert_eq!(check("4 5 4\n\n2 1 1+"), None);
+        assert_eq!(
+            check("1 7\n\n  1"),
+            Some(Infeasibility::OverfullIsland((2, 0)))
+        );
+        assert_eq!(
+            check("1 1\n\n 2"),
+            Some(Infeasibility::UnreachableIsland((1, 2)))
+        );
+        assert_eq!(check("2"), Some(Infeasibility::OverfullIsland((0, 0))));
+
+        // The solver checks these up front rather than searching every branch.
+        let b = Board::parse("4 5 4\n\n2 1 1").unwrap();
+        assert_eq!(
+            SolveState::new(&b).solve_with(&SolveOptions::default()),
+            Err("clues add up to an odd number")
+        );
+    }
+
+    #[test]
+    fn test_explain_unsolvable() {
+        let b = Board::parse("4 1\n\n\n\n3^").unwrap();
+        let certificate = explain_unsolvable(&SolveState::new(&b), 1).unwrap();
+        assert_eq!(
+            certificate.to_string(),
+            "bridge between (2, 0) and (0, 0): only viable edge\n\
+             bridge between (0, 4) and (0, 0): only viable edge\n\
+             the island at (0, 0) needs another bridge, but each one fails:\n\
+             - with a bridge to (0, 4):\n  \
+             the 4 at (0, 0) can no longer be completed\n"
+        );
+        // Too few case splits to show it.
+        assert_eq!(explain_unsolvable(&SolveState::new(&b), 0), None);
+
+        let b = Board::parse("3 3 2\n\n1   1").unwrap();
+        assert_eq!(explain_unsolvable(&SolveState::new(&b), 3), None);
+        let b = Board::parse("4 5 4\n\n2 1 1").unwrap();
+        assert_eq!(
+            explain_unsolvable(&SolveState::new(&b), 0),
+            Some(Certificate::Infeasible(Infeasibility::OddClueSum))
+        );
+    }
+}
@@ -0,0 +1,81 @@
+//! Random boards for fuzzers, built from the fuzzer's bytes. Every board has its islands inside
+//! the board, at most one to a cell, with clues no island could be given more than, but most have
+//! no solution.
+
+use alloc::vec::Vec;
+
+use arbitrary::{Arbitrary, Result, Unstructured};
+
+use crate::{Board, BoardTopology, BoardVariant, ClueKind, Node};
+
+// Keeps boards small enough to be worth fuzzing the solver with.
+const MAX_SIZE: usize = 16;
+
+impl<'a> Arbitrary<'a> for Board {
+    fn arbitrary(u: &mut Unstructured<'a>) -> Result<Self> {
+        let variant = BoardVariant {
+            max_bridges: u.int_in_range(1..=3)?,
+            diagonal: u.arbitrary()?,
+            topology: if u.arbitrary()? {
+                BoardTopology::Torus
+            } else {
+                BoardTopology::Plane
+            },
+        };
+        let size = (u.int_in_range(1..=MAX_SIZE)?, u.int_in_range(1..=MAX_SIZE)?);
+        let directions = if variant.diagonal { 8 } else { 4 };
+        let most = directions * variant.max_bridges;
+
+        let mut nodes = Vec::new();
+        for y in 0..size.1 {
+            for x in 0..size.0 {
+                if !u.ratio(1, 3)? {
+                    continue;
+                }
+                let (n, kind) = match u.int_in_range(0..=8)? {
+                    0 => (None, ClueKind::Exact),
+                    1 => (Some(u.int_in_range(1..=most)?), ClueKind::AtLeast),
+                    2 => (Some(u.int_in_range(1..=most)?), ClueKind::AtMost),
+                    _ => (Some(u.int_in_range(1..=most)?), ClueKind::Exact),
+                };
+                nodes.push(Node {
+                    n,
+                    kind,
+                    pos: (x, y),
+                });
+            }
+        }
+        Ok(Board::new_sized(nodes, size, variant))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{SolveOptions, SolveState};
+
+    #[test]
+    fn test_arbitrary_board() {
+        let mut seen = 0;
+        for seed in 0..32u8 {
+            let bytes = (0..4096)
+                .map(|i: u32| (i.wrapping_mul(2654435761) >> 13) as u8 ^ seed)
+                .collect::<Vec<_>>();
+            let b = Board::arbitrary(&mut Unstructured::new(&bytes)).unwrap();
+            let (w, h) = b.size();
+            for (i, node) in b.nodes.iter().enumerate() {
+                assert!(node.pos.0 < w && node.pos.1 < h);
+                assert!(b.nodes[..i].iter().all(|n| n.pos != node.pos));
+            }
+            seen += b.nodes.len();
+
+            // Whatever it is, the solver copes with it.
+            let options = SolveOptions {
+                max_visited: 100,
+                ..Default::default()
+            };
+            let _ = SolveState::new(&b).solve_with(&options);
+        }
+        assert!(seen > 0);
+    }
+}
@@ -0,0 +1,143 @@
+//! A version of the search which periodically yields to the executor and can be cancelled,
+//! enabled with the `async` feature. It doesn't depend on any particular runtime.
+
+use alloc::vec::Vec;
+use core::future::{poll_fn, Future};
+use core::pin::pin;
+use core::task::Poll;
+
+use crate::{SolveOptions, SolveState};
+
+impl SolveState<'_> {
+    // Like `solve_with`, with the same options, but yields before every speculative edge. If
+    // `cancel` completes first, the search is abandoned and the state is put back the way it was.
+    pub async fn solve_async(
+        &mut self,
+        options: &SolveOptions,
+        cancel: impl Future<Output = ()>,
+    ) -> Result<(Vec<usize>, Vec<&'static str>), &'static str> {
+        let (start, depth, branches) = (self.soln.len(), self.depth, self.branches.len());
+        let decisions = self.decisions.len();
+
+        let ret = {
+            let mut cancel = pin!(cancel);
+            let mut solve = pin!(self.search_with(options, true));
+            poll_fn(|cx| {
+                if cancel.as_mut().poll(cx).is_ready() {
+                    return Poll::Ready(Err("cancelled"));
+                }
+                solve.as_mut().poll(cx)
+            })
+            .await
+        };
+
+        if ret == Err("cancelled") {
+            while self.soln.len() > start {
+                self.remove_edge(*self.soln.last().unwrap());
+            }
+            self.depth = depth;
+            // Any branches being recorded are left pending.
+            self.branches.truncate(branches);
+            self.decisions.truncate(decisions);
+        }
+        ret
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tests::{MEDIUM_10X10, MEDIUM_12X12};
+    use crate::{Board, YieldNow};
+    use core::task::Context;
+    use std::sync::Arc;
+    use std::task::{Wake, Waker};
+
+    struct NoopWaker;
+
+    impl Wake for NoopWaker {
+        fn wake(self: Arc<Self>) {}
+    }
+
+    fn block_on<T>(fut: impl Future<Output = T>) -> (T, usize) {
+        let mut fut = pin!(fut);
+        let waker = Waker::from(Arc::new(NoopWaker));
+        let mut cx = Context::from_waker(&waker);
+        let mut polls = 1;
+        loop {
+            match fut.as_mut().poll(&mut cx) {
+                Poll::Ready(v) => return (v, polls),
+                Poll::Pending => polls += 1,
+            }
+        }
+    }
+
+    #[test]
+    fn test_solve_async() {
+        let b = Board::parse(MEDIUM_10X10).unwrap();
+        let options = SolveOptions::default();
+
+        let (ret, polls) =
+            block_on(SolveState::new(&b).solve_async(&options, core::future::pending()));
+        assert_eq!(ret, SolveState::new(&b).solve_with(&options));
+        assert!(polls > 1);
+
+        // Services will want to spawn the search onto a multithreaded executor.
+        fn assert_send<T: Send>(v: T) -> T {
+            v
+        }
+
+        let mut state = SolveState::new(&b);
+        let (ret, _) = block_on(assert_send(state.solve_async(&options, YieldNow(false))));
+        assert_eq!(ret, Err("cancelled"));
+        assert!(state.soln.is_empty());
+    }
+
+    #[test]
+    fn test_solve_async_restarts() {
+        let b = Board::parse(MEDIUM_12X12).unwrap();
+        let solve = |options| {
+            let mut state = SolveState::new(&b);
+            let (ret, _) = block_on(state.solve_async(&options, core::future::pending()));
+            (ret, state)
+        };
+
+        // The first attempt gets no states, but the second gets one more.
+        let (ret, _) = solve(SolveOptions {
+            max_visited: 0,
+            ..Default::default()
+        });
+        assert!(ret.is_err());
+        let (ret, state) = solve(SolveOptions {
+            max_visited: 1,
+            restarts: 1,
+            ..Default::default()
+        });
+        assert!(ret.is_ok());
+        assert!(state.conflicts.iter().any(|count| *count > 0));
+    }
+
+    #[test]
+    fn test_solve_async_paranoid() {
+        let b = Board::parse(MEDIUM_10X10).unwrap();
+        let options = SolveOptions {
+            paranoid: true,
+            ..Default::default()
+        };
+        let (soln, log) = SolveState::new(&b).solve_with(&options).unwrap();
+
+        // A guess passed off as a deduction still gives a valid solution, but not a derivation
+        // which holds up.
+        let guess = soln[log.iter().position(|r| *r == "speculative").unwrap()];
+        let mut state = SolveState::new(&b);
+        state.add_edge(guess, "only viable edge");
+        let (ret, _) = block_on(state.solve_async(&options, core::future::pending()));
+        assert_eq!(ret, Err("solver failed its own check"));
+        assert_eq!(state.self_check_error().map(|e| e.step), Some(0));
+
+        let mut state = SolveState::new(&b);
+        let (ret, _) = block_on(state.solve_async(&options, core::future::pending()));
+        assert_eq!(ret, Ok((soln, log)));
+        assert_eq!(state.self_check_error(), None);
+    }
+}
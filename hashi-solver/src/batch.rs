@@ -0,0 +1,58 @@
+//! Solving many independent boards at once, spread across the rayon thread pool. Enabled with the
+//! `parallel` feature.
+
+use std::time::{Duration, Instant};
+
+use rayon::prelude::*;
+
+use crate::{Board, SolveOptions, SolveState, SolveStats};
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SolveResult {
+    pub result: Result<(Vec<usize>, Vec<&'static str>), &'static str>,
+    pub stats: SolveStats,
+    pub duration: Duration,
+}
+
+// Solve every board, returning the results in the same order as the boards.
+pub fn solve_all(boards: &[Board], options: &SolveOptions) -> Vec<SolveResult> {
+    boards
+        .par_iter()
+        .map(|board| {
+            let mut state = SolveState::new(board);
+            let start = Instant::now();
+            let result = state.solve_with(options);
+            SolveResult {
+                duration: start.elapsed(),
+                stats: state.stats(),
+                result,
+            }
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_solve_all() {
+        let boards = (0..16)
+            .map(|i| {
+                if i % 4 == 0 {
+                    Board::parse("3 6 3").unwrap()
+                } else {
+                    Board::parse("2 4 2").unwrap()
+                }
+            })
+            .collect::<Vec<_>>();
+        let results = solve_all(&boards, &SolveOptions::default());
+
+        assert_eq!(results.len(), boards.len());
+        for (i, r) in results.iter().enumerate() {
+            assert_eq!(r.result.is_ok(), i % 4 != 0);
+        }
+        assert_eq!(results[1].result.as_ref().unwrap().0.len(), 4);
+        assert_eq!(results[1].stats.deductions, 4);
+    }
+}
@@ -0,0 +1,169 @@
+//! Timing and search statistics for solving a set of boards, independent of any benchmarking
+//! framework.
+
+use std::fmt;
+use std::io::{self, Write};
+use std::path::Path;
+use std::time::{Duration, Instant};
+
+use crate::{Board, SolveOptions, SolveState};
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BenchResult {
+    pub solved: bool,
+    pub duration: Duration,
+    // Board states recorded by the search before it finished.
+    pub states_visited: usize,
+    // Bridges placed by deduction and by speculation in the final solution. Both are zero if the
+    // board wasn't solved.
+    pub deductions: usize,
+    pub speculations: usize,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct BenchReport {
+    // One result per board, in the order they were given.
+    pub results: Vec<BenchResult>,
+}
+
+impl BenchReport {
+    pub fn solved(&self) -> usize {
+        self.results.iter().filter(|r| r.solved).count()
+    }
+
+    pub fn total_duration(&self) -> Duration {
+        self.results.iter().map(|r| r.duration).sum()
+    }
+
+    pub fn mean_duration(&self) -> Duration {
+        if self.results.is_empty() {
+            return Duration::ZERO;
+        }
+        self.total_duration() / self.results.len() as u32
+    }
+
+    pub fn max_duration(&self) -> Duration {
+        self.results
+            .iter()
+            .map(|r| r.duration)
+            .max()
+            .unwrap_or_default()
+    }
+
+    pub fn states_visited(&self) -> usize {
+        self.results.iter().map(|r| r.states_visited).sum()
+    }
+
+    pub fn deductions(&self) -> usize {
+        self.results.iter().map(|r| r.deductions).sum()
+    }
+
+    pub fn speculations(&self) -> usize {
+        self.results.iter().map(|r| r.speculations).sum()
+    }
+}
+
+impl fmt::Display for BenchReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "solved {}/{}", self.solved(), self.results.len())?;
+        writeln!(
+            f,
+            "time: total {:?}, mean {:?}, max {:?}",
+            self.total_duration(),
+            self.mean_duration(),
+            self.max_duration()
+        )?;
+        writeln!(
+            f,
+            "states visited: {}, deductions: {}, speculations: {}",
+            self.states_visited(),
+            self.deductions(),
+            self.speculations()
+        )
+    }
+}
+
+pub fn run(boards: &[Board], options: &SolveOptions) -> BenchReport {
+    let results = boards
+        .iter()
+        .map(|board| measure(SolveState::new(board), options))
+        .collect();
+
+    BenchReport { results }
+}
+
+// Time solving from `state`, which may have been set up beforehand, e.g. with techniques.
+pub(crate) fn measure(mut state: SolveState, options: &SolveOptions) -> BenchResult {
+    let start = Instant::now();
+    let ret = state.solve_with(options);
+    let duration = start.elapsed();
+
+    let stats = state.stats();
+    let solved = ret.is_ok();
+    BenchResult {
+        solved,
+        duration,
+        states_visited: stats.states_visited,
+        deductions: if solved { stats.deductions } else { 0 },
+        speculations: if solved { stats.speculations } else { 0 },
+    }
+}
+
+// Write one row per board, in the order of `report.results`, for analysis in a spreadsheet or
+// dataframe. The `board` column is the board's index in the list given to `run`.
+pub fn write_csv(report: &BenchReport, mut w: impl Write) -> io::Result<()> {
+    writeln!(
+        w,
+        "board,solved,duration_ns,states_visited,deductions,speculations"
+    )?;
+    for (idx, r) in report.results.iter().enumerate() {
+        writeln!(
+            w,
+            "{},{},{},{},{},{}",
+            idx,
+            r.solved,
+            r.duration.as_nanos(),
+            r.states_visited,
+            r.deductions,
+            r.speculations
+        )?;
+    }
+    Ok(())
+}
+
+pub fn export_csv(report: &BenchReport, path: impl AsRef<Path>) -> io::Result<()> {
+    let mut w = io::BufWriter::new(std::fs::File::create(path)?);
+    write_csv(report, &mut w)?;
+    w.flush()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_run() {
+        let boards = [
+            Board::parse("2 4 2").unwrap(),
+            Board::parse("3 6 3").unwrap(),
+        ];
+        let report = run(&boards, &SolveOptions::default());
+
+        assert_eq!(report.results.len(), 2);
+        assert_eq!(report.solved(), 1);
+        assert!(report.results[0].solved);
+        assert_eq!(report.results[0].deductions, 4);
+        assert_eq!(report.results[0].speculations, 0);
+        assert!(!report.results[1].solved);
+        assert_eq!(report.results[1].deductions, 0);
+
+        let mut csv = vec![];
+        write_csv(&report, &mut csv).unwrap();
+        let csv = String::from_utf8(csv).unwrap();
+        let lines = csv.lines().collect::<Vec<_>>();
+        assert_eq!(lines.len(), 3);
+        assert!(lines[1].starts_with("0,true,"));
+        assert!(lines[1].ends_with(",4,0"));
+        assert!(lines[2].starts_with("1,false,"));
+    }
+}
@@ -1,17 +1,203 @@
-use hashi_solver::{Board, SolveState};
+use hashi_solver::{
+    formats, regression, Board, RenderStyle, SolveEvent, SolveOptions, SolveOutcome, SolveState,
+};
 use std::io::Read;
 
 fn main() {
+    // Compare the solver's results on a directory of boards with those recorded for them, or
+    // record them with `--regression-record`.
+    let args = std::env::args().collect::<Vec<_>>();
+    if let Some(i) = args
+        .iter()
+        .position(|arg| arg == "--regression" || arg == "--regression-record")
+    {
+        let dir = args.get(i + 1).expect("expected a corpus directory");
+        if args[i] == "--regression-record" {
+            let boards = regression::record(dir).unwrap();
+            println!("recorded {} boards", boards);
+            return;
+        }
+        let report = regression::run(dir).unwrap();
+        print!("{}", report);
+        if !report.passed() {
+            std::process::exit(1);
+        }
+        return;
+    }
+
+    // Add `count` boards generated from seeds starting at `seed` to a catalog, as with
+    // `--catalog-generate puzzles.txt 10 0`, or solve every board in one with `--catalog-batch`.
+    if let Some(i) = args.iter().position(|arg| arg == "--catalog-generate") {
+        let path = args.get(i + 1).expect("expected a catalog");
+        let count = args
+            .get(i + 2)
+            .map_or(1, |n| n.parse().expect("invalid count"));
+        let seed = args
+            .get(i + 3)
+            .map_or(0, |n| n.parse().expect("invalid seed"));
+        catalog_generate(path, count, seed);
+        return;
+    }
+    if let Some(i) = args.iter().position(|arg| arg == "--catalog-batch") {
+        catalog_batch(args.get(i + 1).expect("expected a catalog"));
+        return;
+    }
+
     let mut s = String::new();
     std::io::stdin().read_to_string(&mut s).unwrap();
-    println!("solving...");
+    let b = Board::parse_any(&s).unwrap();
+
+    // Show the board as it was parsed, to check it against what was meant before solving.
+    if std::env::args().any(|arg| arg == "--echo-normalized") {
+        let (w, h) = b.size();
+        println!("{}x{} board:", w, h);
+        print!("{}", b.normalize_text());
+        return;
+    }
 
-    let b = Board::parse(&s).unwrap();
-    let (soln, log) = SolveState::new(&b).solve(3, 10_000).unwrap();
+    // The search as it ran, backtracks and all, for debugging the solver.
+    if std::env::args().any(|arg| arg == "--trace") {
+        let mut state = SolveState::new(&b).with_search_trace();
+        let result = state.solve_with(&SolveOptions::default());
+        for event in state.search_trace().unwrap() {
+            match *event {
+                SolveEvent::EdgeAdded { edge, reason } => {
+                    let ((x1, y1), (x2, y2)) = b.edge_endpoints(edge);
+                    println!("+ ({}, {}) - ({}, {}): {}", x1, y1, x2, y2, reason);
+                }
+                SolveEvent::EdgeRemoved { edge } => {
+                    let ((x1, y1), (x2, y2)) = b.edge_endpoints(edge);
+                    println!("- ({}, {}) - ({}, {})", x1, y1, x2, y2);
+                }
+                SolveEvent::Solved => println!("solved"),
+            }
+        }
+        if let Err(e) = result {
+            println!("failed: {}", e);
+            std::process::exit(2);
+        }
+        return;
+    }
+
+    // A save file is printed on its own, so that it can be written straight to a file.
+    let sgt_save = std::env::args().any(|arg| arg == "--sgt-save");
+    if !sgt_save {
+        let metadata = b.metadata();
+        for (label, value) in [
+            ("id", &metadata.id),
+            ("title", &metadata.title),
+            ("author", &metadata.author),
+            ("source", &metadata.source_url),
+            ("difficulty", &metadata.difficulty),
+        ] {
+            if let Some(value) = value {
+                println!("{}: {}", label, value);
+            }
+        }
+
+        println!("solving...");
+    }
+    let (soln, log) = match SolveState::new(&b).solve_outcome(&SolveOptions::default()) {
+        SolveOutcome::Solved { solution, log } => b.minimal_proof(&solution.edges, &log),
+        SolveOutcome::ProvedUnsolvable(certificate) => {
+            print!("no solution:\n{}", certificate);
+            std::process::exit(1);
+        }
+        SolveOutcome::Inconclusive(limit) => {
+            println!("gave up: {}", limit);
+            std::process::exit(2);
+        }
+    };
+
+    // The solution as a save file to open in Simon Tatham's Bridges.
+    if sgt_save {
+        match formats::to_sgt_save(&b, &b.moves(soln.iter().copied())) {
+            Ok(save) => print!("{}", save),
+            Err(e) => {
+                println!("can't save for Bridges: {}", e);
+                std::process::exit(2);
+            }
+        }
+        return;
+    }
 
-    for i in 0..soln.len() {
-        println!("{}", log[i]);
-        println!("{}", b.serialize_to_string(soln.iter().copied().take(i)));
+    // For terminals which draw some of the classic bridge characters two columns wide.
+    let style = if std::env::args().any(|arg| arg == "--ascii") {
+        RenderStyle::Ascii
+    } else {
+        RenderStyle::Classic
+    };
+    for (i, step) in b.steps(&soln, &log).iter().enumerate() {
+        println!("{}", step);
+        let placed = soln.iter().copied().take(i);
+        println!("{}", b.serialize_styled_to_string(placed, style));
         println!();
     }
+
+    for ((x, y), n) in b.wildcard_values(soln.iter().copied()) {
+        println!("? at ({}, {}) is {}", x, y, n);
+    }
+}
+
+#[cfg(feature = "generator")]
+fn catalog_generate(path: &str, count: u64, seed: u64) {
+    let mut catalog = hashi_solver::catalog::Catalog::open(path).unwrap();
+    let options = hashi_solver::generator::GenerateOptions::default();
+    let mut added = 0;
+    // Each seed tries up to `max_candidates` seeds after it, so they're spaced out to keep the
+    // boards from repeating.
+    let seeds = (0..count).map(|i| seed.wrapping_add(i * options.max_candidates as u64));
+    for seed in seeds {
+        match hashi_solver::generator::generate(seed, &options) {
+            Some((b, _)) => {
+                // Different seeds can come up with the same board, which is only kept once.
+                if catalog.add(&b, &format!("seed {}", seed)).is_ok() {
+                    added += 1;
+                }
+            }
+            None => println!("seed {}: no board found", seed),
+        }
+    }
+    catalog.save().unwrap();
+    println!("added {} boards", added);
+}
+
+#[cfg(not(feature = "generator"))]
+fn catalog_generate(_: &str, _: u64, _: u64) {
+    println!("built without the `generator` feature");
+    std::process::exit(2);
+}
+
+#[cfg(feature = "parallel")]
+fn catalog_batch(path: &str) {
+    let catalog = hashi_solver::catalog::Catalog::open(path).unwrap();
+    let boards = catalog
+        .entries()
+        .iter()
+        .map(hashi_solver::catalog::Entry::board)
+        .collect::<Vec<_>>();
+    let results = hashi_solver::batch::solve_all(&boards, &SolveOptions::default());
+    let mut failed = 0;
+    for (entry, r) in catalog.entries().iter().zip(&results) {
+        match r.result {
+            Ok(_) => println!(
+                "{}: solved in {:?}, {} states",
+                entry.id, r.duration, r.stats.states_visited
+            ),
+            Err(e) => {
+                println!("{}: {}", entry.id, e);
+                failed += 1;
+            }
+        }
+    }
+    println!("{} of {} solved", results.len() - failed, results.len());
+    if failed > 0 {
+        std::process::exit(1);
+    }
+}
+
+#[cfg(not(feature = "parallel"))]
+fn catalog_batch(_: &str) {
+    println!("built without the `parallel` feature");
+    std::process::exit(2);
 }
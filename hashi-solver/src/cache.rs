@@ -0,0 +1,156 @@
+//! Solutions of boards kept around between calls, for workflows like hints and mistake checks
+//! which would otherwise solve the same board from scratch after every move.
+
+use alloc::collections::BTreeMap;
+use alloc::string::String;
+use alloc::vec::Vec;
+
+use crate::{Board, Solution, SolveOptions, SolveState, SolveStats};
+
+// A board's solution, along with how the solver got there.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CachedSolve {
+    pub solution: Solution,
+    // The reason for each edge of the solution, in the same order.
+    pub log: Vec<&'static str>,
+    // How much work the solve took, as a rough rating of the board.
+    pub stats: SolveStats,
+}
+
+// Solutions keyed by `Board::canonical_id`. Failed solves aren't kept, since they may succeed
+// with more generous options.
+#[derive(Debug, Clone, Default)]
+pub struct SolverCache {
+    solves: BTreeMap<String, CachedSolve>,
+    pub hits: usize,
+    pub misses: usize,
+}
+
+impl SolverCache {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn get(&self, board: &Board) -> Option<&CachedSolve> {
+        self.solves.get(&board.canonical_id())
+    }
+
+    pub fn solve(
+        &mut self,
+        board: &Board,
+        options: &SolveOptions,
+    ) -> Result<&CachedSolve, &'static str> {
+        let id = board.canonical_id();
+        if self.solves.contains_key(&id) {
+            self.hits += 1;
+        } else {
+            self.misses += 1;
+            let mut state = SolveState::new(board);
+            let (edges, log) = state.solve_with(options)?;
+            let solve = CachedSolve {
+                solution: Solution { edges },
+                log,
+                stats: state.stats(),
+            };
+            self.solves.insert(id.clone(), solve);
+        }
+        Ok(&self.solves[&id])
+    }
+
+    // Like `SolveState::hint`, but taking the next bridge from the cached solution when there's
+    // nothing to deduce. If the state has strayed from that solution, it's solved afresh.
+    pub fn hint(
+        &mut self,
+        state: &SolveState,
+        options: &SolveOptions,
+    ) -> Result<(usize, &'static str), &'static str> {
+        if state.solved() {
+            return Err("already solved");
+        }
+        state.solvable().map_err(|c| c.check.reason())?;
+        if let Some(v) = state.solve_fully_constrained() {
+            return Ok(v);
+        }
+
+        let solve = self.solve(state.board, options)?;
+        if !state.mistakes_against(&solve.solution.edges).is_empty() {
+            return state.hint(options.max_depth, options.max_visited);
+        }
+        // The first bridge of the solution which hasn't been placed yet.
+        let mut placed = state.edge_counts.clone();
+        for (edge, reason) in solve.solution.edges.iter().zip(&solve.log) {
+            if placed[*edge].count() == 0 {
+                return Ok((*edge, reason));
+            }
+            placed[*edge].decrement();
+        }
+        Err("already solved")
+    }
+
+    // Like `SolveState::mistakes`, checked against the cached solution.
+    pub fn mistakes(
+        &mut self,
+        state: &SolveState,
+        options: &SolveOptions,
+    ) -> Result<Vec<usize>, &'static str> {
+        let solve = self.solve(state.board, options)?;
+        Ok(state.mistakes_against(&solve.solution.edges))
+    }
+
+    pub fn len(&self) -> usize {
+        self.solves.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.solves.is_empty()
+    }
+
+    pub fn clear(&mut self) {
+        self.solves.clear();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Move;
+
+    #[test]
+    fn test_solver_cache() {
+        let b = Board::parse("3 3 2\n\n1   1").unwrap();
+        let options = SolveOptions::default();
+        let mut cache = SolverCache::new();
+        let first = cache.solve(&b, &options).unwrap().clone();
+        let moved = Board::parse("\n 3 3 2\n\n 1   1").unwrap();
+        assert_eq!(cache.solve(&moved, &options).unwrap(), &first);
+        assert_eq!((cache.hits, cache.misses, cache.len()), (1, 1, 1));
+
+        let mut state = SolveState::new(&b);
+        assert_eq!(
+            cache.hint(&state, &options),
+            state.hint(options.max_depth, options.max_visited)
+        );
+
+        let mv = Move {
+            from: (0, 0),
+            to: (0, 2),
+            count: 1,
+        };
+        state.add_move(mv, "user").unwrap();
+        assert_eq!(cache.mistakes(&state, &options), Ok(vec![]));
+        assert_eq!(cache.misses, 1);
+
+        // Once the deductions run out, the hint comes from the cached solution.
+        let b = Board::parse("3 3\n\n3 3").unwrap();
+        let mut state = SolveState::new(&b);
+        while let Some((edge, reason)) = state.solve_fully_constrained() {
+            state.add_edge(edge, reason);
+        }
+        let (edge, reason) = cache.hint(&state, &options).unwrap();
+        assert_eq!(reason, "speculative");
+        assert_eq!(
+            Ok((edge, reason)),
+            state.hint(options.max_depth, options.max_visited)
+        );
+    }
+}
@@ -0,0 +1,407 @@
+//! A collection of puzzles with metadata, kept in a single file or as one file per puzzle in a
+//! directory. Each puzzle is written as a few `key: value` lines, a blank line, then the clues in
+//! the format accepted by `Board::parse`; in a single file, puzzles are separated by `---` lines.
+//! The variant and size are part of the header, so boards come back exactly as they were added.
+
+use std::fmt::{self, Write};
+use std::io;
+use std::path::{Path, PathBuf};
+
+use crate::analyze::estimate_hardness;
+use crate::{Board, BoardMetadata, BoardTopology, BoardVariant};
+
+// Puzzle files in a directory catalog end in this.
+pub const EXTENSION: &str = "hashi";
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Entry {
+    // Derived from `Board::canonical_id`, so the same puzzle always gets the same id.
+    pub id: String,
+    pub size: (usize, usize),
+    // `analyze::HardnessEstimate::score` of the board when it was added.
+    pub difficulty: f64,
+    // Where the puzzle came from, e.g. a generator seed or a book.
+    pub source: String,
+    pub solved: bool,
+    pub variant: BoardVariant,
+    // The board's own metadata, e.g. from a JSON puzzle, as opposed to the catalog's.
+    pub metadata: BoardMetadata,
+    // The clues, as given by `Board::normalize_text`.
+    pub puzzle: String,
+}
+
+impl Entry {
+    pub fn new(board: &Board, source: &str) -> Self {
+        Entry {
+            id: id_for(board),
+            size: board.size(),
+            // Rounded as it's written out, so that entries read back the same.
+            difficulty: (estimate_hardness(board).score() * 1000.0).round() / 1000.0,
+            source: source.into(),
+            solved: false,
+            variant: board.variant,
+            metadata: board.metadata().clone(),
+            puzzle: board.normalize_text(),
+        }
+    }
+
+    pub fn board(&self) -> Board {
+        build(&self.puzzle, self.variant, self.size)
+            .expect("catalog puzzles are checked when they're read")
+            .with_metadata(self.metadata.clone())
+    }
+
+    fn parse(s: &str) -> Result<Self, &'static str> {
+        let (header, puzzle) = s
+            .split_once("\n\n")
+            .ok_or("missing blank line before puzzle")?;
+        let mut entry = Entry {
+            id: String::new(),
+            size: (0, 0),
+            difficulty: 0.0,
+            source: String::new(),
+            solved: false,
+            variant: BoardVariant::default(),
+            metadata: BoardMetadata::default(),
+            puzzle: puzzle.into(),
+        };
+        let mut size = None;
+        for line in header.lines() {
+            let (key, value) = line.split_once(": ").ok_or("expected `key: value`")?;
+            match key {
+                "id" => entry.id = value.into(),
+                "size" => {
+                    let (w, h) = value.split_once('x').ok_or("expected a size like 7x7")?;
+                    size = Some((
+                        w.parse().map_err(|_| "invalid width")?,
+                        h.parse().map_err(|_| "invalid height")?,
+                    ));
+                }
+                "variant" => entry.variant = parse_variant(value)?,
+                "difficulty" => {
+                    entry.difficulty = value.parse().map_err(|_| "invalid difficulty")?
+                }
+                "source" => entry.source = value.into(),
+                "solved" => entry.solved = value == "yes",
+                "puzzle_id" => entry.metadata.id = Some(value.into()),
+                "title" => entry.metadata.title = Some(value.into()),
+                "author" => entry.metadata.author = Some(value.into()),
+                "source_url" => entry.metadata.source_url = Some(value.into()),
+                "stated_difficulty" => entry.metadata.difficulty = Some(value.into()),
+                // Keys written by newer versions are skipped.
+                _ => {}
+            }
+        }
+        if entry.id.is_empty() {
+            return Err("missing id");
+        }
+        // Entries from before sizes were written take the size of their clues.
+        entry.size = match size {
+            Some(size) => size,
+            None => Board::parse_variant(&entry.puzzle, entry.variant)
+                .map_err(|e| e.message)?
+                .size(),
+        };
+        build(&entry.puzzle, entry.variant, entry.size)?;
+        Ok(entry)
+    }
+}
+
+fn build(puzzle: &str, variant: BoardVariant, size: (usize, usize)) -> Result<Board, &'static str> {
+    let parsed = Board::parse_variant(puzzle, variant).map_err(|e| e.message)?;
+    let nodes = parsed.nodes.to_vec();
+    if nodes.iter().any(|n| n.pos.0 >= size.0 || n.pos.1 >= size.1) {
+        return Err("island outside the board");
+    }
+    Ok(Board::new_sized(nodes, size, variant))
+}
+
+// The bridge count followed by any of `diagonal` and `torus`, as written by `Entry`'s `Display`.
+fn parse_variant(value: &str) -> Result<BoardVariant, &'static str> {
+    let mut words = value.split(' ');
+    let max_bridges = words
+        .next()
+        .and_then(|w| w.parse().ok())
+        .filter(|m| (1..=3).contains(m))
+        .ok_or("invalid bridge count")?;
+    let mut variant = BoardVariant {
+        max_bridges,
+        ..Default::default()
+    };
+    for word in words {
+        match word {
+            "diagonal" => variant.diagonal = true,
+            "torus" => variant.topology = BoardTopology::Torus,
+            _ => return Err("unknown variant"),
+        }
+    }
+    Ok(variant)
+}
+
+impl fmt::Display for Entry {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "id: {}", self.id)?;
+        writeln!(f, "size: {}x{}", self.size.0, self.size.1)?;
+        write!(f, "variant: {}", self.variant.max_bridges)?;
+        if self.variant.diagonal {
+            write!(f, " diagonal")?;
+        }
+        if self.variant.topology == BoardTopology::Torus {
+            write!(f, " torus")?;
+        }
+        writeln!(f)?;
+        writeln!(f, "difficulty: {:.3}", self.difficulty)?;
+        writeln!(f, "source: {}", self.source)?;
+        writeln!(f, "solved: {}", if self.solved { "yes" } else { "no" })?;
+        let metadata = &self.metadata;
+        for (key, value) in [
+            ("puzzle_id", &metadata.id),
+            ("title", &metadata.title),
+            ("author", &metadata.author),
+            ("source_url", &metadata.source_url),
+            ("stated_difficulty", &metadata.difficulty),
+        ] {
+            if let Some(value) = value {
+                writeln!(f, "{}: {}", key, value)?;
+            }
+        }
+        writeln!(f)?;
+        write!(f, "{}", self.puzzle)
+    }
+}
+
+// A 64-bit FNV-1a hash of the canonical id, in hex.
+fn id_for(board: &Board) -> String {
+    let mut hash = 0xcbf2_9ce4_8422_2325u64;
+    for byte in board.canonical_id().bytes() {
+        hash ^= byte as u64;
+        hash = hash.wrapping_mul(0x0100_0000_01b3);
+    }
+    format!("{:016x}", hash)
+}
+
+// Which entries to pick out of a catalog. Unset fields match everything.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Filter {
+    pub min_difficulty: Option<f64>,
+    pub max_difficulty: Option<f64>,
+    // Boards no wider or taller than this.
+    pub max_size: Option<(usize, usize)>,
+    pub source: Option<String>,
+    pub solved: Option<bool>,
+}
+
+impl Filter {
+    pub fn matches(&self, entry: &Entry) -> bool {
+        self.min_difficulty.is_none_or(|d| entry.difficulty >= d)
+            && self.max_difficulty.is_none_or(|d| entry.difficulty <= d)
+            && self
+                .max_size
+                .is_none_or(|(w, h)| entry.size.0 <= w && entry.size.1 <= h)
+            && self.source.as_ref().is_none_or(|s| entry.source == *s)
+            && self.solved.is_none_or(|s| entry.solved == s)
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Catalog {
+    path: PathBuf,
+    // Whether `path` is a directory of puzzle files, rather than a single file.
+    dir: bool,
+    entries: Vec<Entry>,
+}
+
+impl Catalog {
+    // Open the catalog at `path`, which is read as a directory if it is one. A file which
+    // doesn't exist yet is treated as an empty catalog.
+    pub fn open(path: impl AsRef<Path>) -> io::Result<Self> {
+        let path = path.as_ref().to_path_buf();
+        let dir = path.is_dir();
+        let mut entries = vec![];
+        if dir {
+            let mut files = std::fs::read_dir(&path)?
+                .map(|e| e.map(|e| e.path()))
+                .collect::<io::Result<Vec<_>>>()?;
+            files.retain(|f| f.extension().is_some_and(|e| e == EXTENSION));
+            files.sort();
+            for file in files {
+                entries.extend(parse_entries(&std::fs::read_to_string(&file)?, &file)?);
+            }
+        } else if path.exists() {
+            entries = parse_entries(&std::fs::read_to_string(&path)?, &path)?;
+        }
+        Ok(Catalog { path, dir, entries })
+    }
+
+    pub fn entries(&self) -> &[Entry] {
+        &self.entries
+    }
+
+    pub fn get(&self, id: &str) -> Option<&Entry> {
+        self.entries.iter().find(|e| e.id == id)
+    }
+
+    pub fn filter<'a>(&'a self, filter: &'a Filter) -> impl Iterator<Item = &'a Entry> {
+        self.entries.iter().filter(|e| filter.matches(e))
+    }
+
+    // Add a board, unless the same puzzle is already in the catalog. Changes aren't written
+    // until `save`.
+    pub fn add(&mut self, board: &Board, source: &str) -> Result<&Entry, &'static str> {
+        let entry = Entry::new(board, source);
+        if self.get(&entry.id).is_some() {
+            return Err("puzzle is already in the catalog");
+        }
+        self.entries.push(entry);
+        Ok(self.entries.last().unwrap())
+    }
+
+    pub fn remove(&mut self, id: &str) -> Option<Entry> {
+        let idx = self.entries.iter().position(|e| e.id == id)?;
+        Some(self.entries.remove(idx))
+    }
+
+    pub fn set_solved(&mut self, id: &str, solved: bool) -> Result<(), &'static str> {
+        let entry = self
+            .entries
+            .iter_mut()
+            .find(|e| e.id == id)
+            .ok_or("no such puzzle in the catalog")?;
+        entry.solved = solved;
+        Ok(())
+    }
+
+    // Write the catalog back to where it was opened from. In a directory, files of puzzles
+    // which have been removed are deleted.
+    pub fn save(&self) -> io::Result<()> {
+        if !self.dir {
+            return std::fs::write(&self.path, export(&self.entries));
+        }
+        for entry in std::fs::read_dir(&self.path)? {
+            let file = entry?.path();
+            let id = file.file_stem().and_then(|s| s.to_str()).unwrap_or("");
+            if file.extension().is_some_and(|e| e == EXTENSION) && self.get(id).is_none() {
+                std::fs::remove_file(file)?;
+            }
+        }
+        for entry in &self.entries {
+            let file = self.path.join(format!("{}.{}", entry.id, EXTENSION));
+            std::fs::write(file, entry.to_string())?;
+        }
+        Ok(())
+    }
+}
+
+// Render entries in the single file format, e.g. to export part of a catalog.
+pub fn export<'a>(entries: impl IntoIterator<Item = &'a Entry>) -> String {
+    let mut s = String::new();
+    for (idx, entry) in entries.into_iter().enumerate() {
+        if idx > 0 {
+            s.push_str("---\n");
+        }
+        write!(s, "{}", entry).unwrap();
+    }
+    s
+}
+
+fn parse_entries(s: &str, file: &Path) -> io::Result<Vec<Entry>> {
+    let mut entries = vec![];
+    let mut chunk = String::new();
+    let lines = s.lines().chain(core::iter::once("---"));
+    for line in lines {
+        if line != "---" {
+            chunk.push_str(line);
+            chunk.push('\n');
+            continue;
+        }
+        if !chunk.trim().is_empty() {
+            let entry = Entry::parse(&chunk).map_err(|e| {
+                let msg = format!("{}: entry {}: {}", file.display(), entries.len() + 1, e);
+                io::Error::new(io::ErrorKind::InvalidData, msg)
+            })?;
+            entries.push(entry);
+        }
+        chunk.clear();
+    }
+    Ok(entries)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_catalog() {
+        let root = std::env::temp_dir().join(format!("hashi-catalog-{}", std::process::id()));
+        std::fs::create_dir_all(&root).unwrap();
+        let easy = Board::parse("2 4 2").unwrap();
+        let harder = Board::parse("3 3 2\n\n1   1").unwrap();
+
+        let file = root.join("puzzles.txt");
+        let mut catalog = Catalog::open(&file).unwrap();
+        let id = catalog.add(&easy, "book").unwrap().id.clone();
+        assert_eq!(
+            catalog.add(&Board::parse("\n 2 4 2").unwrap(), "book"),
+            Err("puzzle is already in the catalog")
+        );
+        catalog.add(&harder, "seed 3").unwrap();
+        catalog.set_solved(&id, true).unwrap();
+        catalog.save().unwrap();
+
+        let reopened = Catalog::open(&file).unwrap();
+        assert_eq!(reopened.entries(), catalog.entries());
+        let unsolved = Filter {
+            solved: Some(false),
+            ..Default::default()
+        };
+        let found = reopened.filter(&unsolved).collect::<Vec<_>>();
+        assert_eq!(found.len(), 1);
+        assert_eq!(found[0].board().canonical_id(), harder.canonical_id());
+
+        let dir = root.join("dir");
+        std::fs::create_dir_all(&dir).unwrap();
+        let mut in_dir = Catalog::open(&dir).unwrap();
+        in_dir.add(&easy, "book").unwrap();
+        in_dir.add(&harder, "seed 3").unwrap();
+        in_dir.save().unwrap();
+        in_dir.remove(&id).unwrap();
+        in_dir.save().unwrap();
+        let reopened = Catalog::open(&dir).unwrap();
+        assert_eq!(reopened.entries(), in_dir.entries());
+        assert_eq!(std::fs::read_dir(&dir).unwrap().count(), 1);
+
+        // The variant, the empty space around a torus and the board's own metadata all survive.
+        let variant = BoardVariant {
+            max_bridges: 3,
+            diagonal: true,
+            topology: BoardTopology::Torus,
+        };
+        let torus = Board::parse_variant("\n 3 3  \n\n", variant)
+            .unwrap()
+            .with_metadata(BoardMetadata {
+                title: Some("Wraparound".into()),
+                difficulty: Some("hard".into()),
+                ..Default::default()
+            });
+        let file = root.join("variants.txt");
+        let mut catalog = Catalog::open(&file).unwrap();
+        let id = catalog.add(&torus, "book").unwrap().id.clone();
+        catalog.save().unwrap();
+        let reopened = Catalog::open(&file).unwrap();
+        assert_eq!(reopened.entries(), catalog.entries());
+        let b = reopened.get(&id).unwrap().board();
+        assert_eq!(b.variant, variant);
+        assert_eq!(b.size(), torus.size());
+        assert_eq!(b.canonical_id(), torus.canonical_id());
+        assert_eq!(b.metadata(), torus.metadata());
+        assert_eq!(id_for(&b), id);
+
+        // Entries written before the variant and size were are read as classic boards.
+        let old = "id: 0123\ndifficulty: 0.5\nsource: book\nsolved: no\n\n2 4 2\n";
+        let entry = Entry::parse(old).unwrap();
+        assert_eq!(entry.variant, BoardVariant::default());
+        assert_eq!(entry.size, (5, 1));
+
+        std::fs::remove_dir_all(&root).unwrap();
+    }
+}
@@ -0,0 +1,150 @@
+//! Solving the same boards under two configurations side by side, e.g. to see what a new
+//! technique does before enabling it by default: how much faster or slower each board gets, which
+//! boards are solved with different numbers of deductions and guesses, and any board which one
+//! configuration solves and the other doesn't.
+
+use std::fmt;
+
+use crate::bench::{measure, BenchResult};
+use crate::techniques::Techniques;
+use crate::{Board, SolveOptions, SolveState};
+
+#[derive(Debug, Copy, Clone, Default, PartialEq)]
+pub struct Config {
+    pub options: SolveOptions,
+    pub techniques: Techniques,
+}
+
+impl From<SolveOptions> for Config {
+    fn from(options: SolveOptions) -> Self {
+        Config {
+            options,
+            ..Default::default()
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Comparison {
+    pub a: BenchResult,
+    pub b: BenchResult,
+}
+
+impl Comparison {
+    // How many times faster `b` solved the board than `a`; below 1 if it was slower.
+    pub fn speedup(&self) -> f64 {
+        self.a.duration.as_secs_f64() / self.b.duration.as_secs_f64().max(1e-9)
+    }
+
+    // Both solved the board, but with different numbers of deductions or guesses.
+    pub fn steps_differ(&self) -> bool {
+        self.a.solved
+            && self.b.solved
+            && (self.a.deductions, self.a.speculations) != (self.b.deductions, self.b.speculations)
+    }
+
+    // One solved the board and the other didn't.
+    pub fn disagrees(&self) -> bool {
+        self.a.solved != self.b.solved
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct CompareReport {
+    // One comparison per board, in the order they were given.
+    pub comparisons: Vec<Comparison>,
+}
+
+impl CompareReport {
+    // The indices of the boards only one configuration solved.
+    pub fn disagreements(&self) -> impl Iterator<Item = usize> + '_ {
+        (0..self.comparisons.len()).filter(|&i| self.comparisons[i].disagrees())
+    }
+
+    // The geometric mean of the speedups, so that halving and doubling cancel out.
+    pub fn mean_speedup(&self) -> f64 {
+        if self.comparisons.is_empty() {
+            return 1.0;
+        }
+        let logs = self
+            .comparisons
+            .iter()
+            .map(|c| c.speedup().ln())
+            .sum::<f64>();
+        (logs / self.comparisons.len() as f64).exp()
+    }
+}
+
+impl fmt::Display for CompareReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let solved = |r: &BenchResult| if r.solved { "solved" } else { "unsolved" };
+        for (i, c) in self.comparisons.iter().enumerate() {
+            write!(f, "board {}: {:.2}x", i, c.speedup())?;
+            if c.disagrees() {
+                write!(f, ", {} vs {}", solved(&c.a), solved(&c.b))?;
+            } else if c.steps_differ() {
+                write!(
+                    f,
+                    ", {} deductions and {} guesses vs {} and {}",
+                    c.a.deductions, c.a.speculations, c.b.deductions, c.b.speculations
+                )?;
+            }
+            writeln!(f)?;
+        }
+        writeln!(
+            f,
+            "mean speedup {:.2}x, {} with different steps, {} disagreements",
+            self.mean_speedup(),
+            self.comparisons.iter().filter(|c| c.steps_differ()).count(),
+            self.disagreements().count()
+        )
+    }
+}
+
+pub fn run(boards: &[Board], a: &Config, b: &Config) -> CompareReport {
+    let solve = |board, config: &Config| {
+        let state = SolveState::new(board).with_techniques(config.techniques);
+        measure(state, &config.options)
+    };
+    let comparisons = boards
+        .iter()
+        .map(|board| Comparison {
+            a: solve(board, a),
+            b: solve(board, b),
+        })
+        .collect();
+    CompareReport { comparisons }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tests::MEDIUM_10X10;
+
+    #[test]
+    fn test_compare() {
+        let boards = [
+            Board::parse("3 3 2\n\n1   1").unwrap(),
+            Board::parse(MEDIUM_10X10).unwrap(),
+        ];
+        let a = Config::from(SolveOptions {
+            max_depth: 0,
+            ..Default::default()
+        });
+        let b = Config {
+            techniques: Techniques::all(),
+            ..Default::default()
+        };
+
+        let report = run(&boards, &a, &b);
+        assert_eq!(report.comparisons.len(), 2);
+        assert!(!report.comparisons[0].disagrees());
+        assert!(!report.comparisons[0].steps_differ());
+        assert!(report.comparisons[1].disagrees());
+        assert_eq!(report.disagreements().collect::<Vec<_>>(), vec![1]);
+        assert!(report.to_string().contains("board 1: "));
+        assert!(report
+            .to_string()
+            .ends_with("0 with different steps, 1 disagreements\n"));
+    }
+}
@@ -0,0 +1,62 @@
+//! Lists of ids for each of a number of items, packed into two flat arrays (the compressed
+//! sparse row layout) rather than allocated separately, for lists which are built once and then
+//! read over and over during a solve.
+
+use alloc::sync::Arc;
+use alloc::vec;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct Csr<T> {
+    // The entries for item `i` are `entries[starts[i]..starts[i + 1]]`.
+    starts: Arc<[u32]>,
+    entries: Arc<[T]>,
+}
+
+impl<T: Copy + Default> Csr<T> {
+    // Build the lists for items `0..len` from `(item, entry)` pairs. Each item's entries are kept
+    // in the order they're given.
+    pub(crate) fn new(len: usize, pairs: &[(usize, T)]) -> Self {
+        let mut starts = vec![0u32; len + 1];
+        for (item, _) in pairs {
+            starts[item + 1] += 1;
+        }
+        for i in 0..len {
+            starts[i + 1] += starts[i];
+        }
+
+        let mut next = starts.clone();
+        let mut entries = vec![T::default(); pairs.len()];
+        for (item, entry) in pairs {
+            entries[next[*item] as usize] = *entry;
+            next[*item] += 1;
+        }
+        Self {
+            starts: starts.into(),
+            entries: entries.into(),
+        }
+    }
+
+    pub(crate) fn bytes(&self) -> usize {
+        self.starts.len() * size_of::<u32>() + self.entries.len() * size_of::<T>()
+    }
+
+    pub(crate) fn get(&self, item: usize) -> impl ExactSizeIterator<Item = T> + Clone + '_ {
+        let range = self.starts[item] as usize..self.starts[item + 1] as usize;
+        self.entries[range].iter().copied()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloc::vec::Vec;
+
+    #[test]
+    fn test_csr() {
+        let csr = Csr::new(4, &[(2, 5u16), (0, 1), (2, 3), (0, 4)]);
+        assert_eq!(csr.get(0).collect::<Vec<_>>(), vec![1, 4]);
+        assert_eq!(csr.get(1).len(), 0);
+        assert_eq!(csr.get(2).collect::<Vec<_>>(), vec![5, 3]);
+        assert_eq!(csr.get(3).len(), 0);
+    }
+}
@@ -0,0 +1,122 @@
+//! Tools for triaging solver bugs. `shrink` cuts a board down to a small reproducer, e.g. from a
+//! generated 40x40 board which the solver gets wrong or is slow on.
+
+use alloc::vec::Vec;
+
+use crate::{Board, Node};
+
+// The smallest board found by removing islands, rows and columns from `board` for which
+// `predicate` still holds, e.g. "the solver still panics" or "solving still takes over a second".
+// Islands are removed in ever smaller groups, so large boards shrink quickly; the order is fixed,
+// so the same board and predicate always give the same result. `board` is returned as it is if
+// the predicate doesn't hold for it to begin with.
+pub fn shrink(board: &Board, mut predicate: impl FnMut(&Board) -> bool) -> Board {
+    let mut best = board.clone();
+    if !predicate(&best) {
+        return best;
+    }
+    loop {
+        let mut changed = false;
+
+        let mut chunk = best.nodes.len().div_ceil(2);
+        while chunk > 0 {
+            let mut start = 0;
+            while start < best.nodes.len() {
+                let mut nodes = best.nodes.to_vec();
+                nodes.drain(start..(start + chunk).min(nodes.len()));
+                let candidate = Board::new_sized(nodes, best.size, best.variant);
+                if predicate(&candidate) {
+                    best = candidate;
+                    changed = true;
+                } else {
+                    start += chunk;
+                }
+            }
+            chunk /= 2;
+        }
+
+        for axis in 0..2 {
+            let mut line = 0;
+            while line < dimension(&best, axis) && dimension(&best, axis) > 1 {
+                let candidate = without_line(&best, axis, line);
+                if predicate(&candidate) {
+                    best = candidate;
+                    changed = true;
+                } else {
+                    line += 1;
+                }
+            }
+        }
+
+        if !changed {
+            return best.with_metadata(board.metadata().clone());
+        }
+    }
+}
+
+// The width of the board for axis 0, and its height for axis 1.
+fn dimension(board: &Board, axis: usize) -> usize {
+    if axis == 0 {
+        board.size.0
+    } else {
+        board.size.1
+    }
+}
+
+// The board without the column (axis 0) or row (axis 1) at `line`, along with any islands in it,
+// and those past it moved in to close the gap.
+fn without_line(board: &Board, axis: usize, line: usize) -> Board {
+    let coord = |pos: (usize, usize)| if axis == 0 { pos.0 } else { pos.1 };
+    let nodes = board
+        .nodes
+        .iter()
+        .filter(|n| coord(n.pos) != line)
+        .map(|n| {
+            let pos = match (axis, coord(n.pos) > line) {
+                (0, true) => (n.pos.0 - 1, n.pos.1),
+                (_, true) => (n.pos.0, n.pos.1 - 1),
+                (_, false) => n.pos,
+            };
+            Node { pos, ..*n }
+        })
+        .collect::<Vec<_>>();
+    let size = if axis == 0 {
+        (board.size.0 - 1, board.size.1)
+    } else {
+        (board.size.0, board.size.1 - 1)
+    };
+    Board::new_sized(nodes, size, board.variant)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tests::MEDIUM_10X10;
+    use crate::SolveState;
+
+    #[test]
+    fn test_shrink() {
+        // The top half of the board, which can't be solved on its own.
+        let top = MEDIUM_10X10.lines().take(7).collect::<Vec<_>>().join("\n");
+        let b = Board::parse(&top).unwrap();
+        let unsolvable_with_a_6 = |b: &Board| {
+            b.nodes.iter().any(|n| n.n == Some(6))
+                && SolveState::new(b).solve(b.edges.len(), 1_000).is_err()
+        };
+        let mut calls = 0;
+        let shrunk = shrink(&b, |b| {
+            calls += 1;
+            unsolvable_with_a_6(b)
+        });
+        assert_eq!(shrunk.to_puzzle_string(), "6\n");
+        assert_eq!(shrunk.size(), (1, 1));
+        assert!(calls < 100);
+
+        // Nothing to shrink if the predicate doesn't hold.
+        let solvable = Board::parse("3 3\n\n3 3").unwrap();
+        assert_eq!(
+            shrink(&solvable, unsolvable_with_a_6).to_puzzle_string(),
+            solvable.to_puzzle_string()
+        );
+    }
+}
@@ -0,0 +1,172 @@
+//! C interface to the solver, enabled with the `ffi` feature. The matching declarations are in
+//! `include/hashi_solver.h`.
+
+use std::ffi::{c_char, CStr, CString};
+use std::panic::{self, AssertUnwindSafe};
+use std::ptr;
+
+use crate::{Board, SolveState};
+
+fn set_error(err: *mut *mut c_char, message: &str) {
+    if !err.is_null() {
+        // Messages come from the solver and never contain interior NULs.
+        let message = CString::new(message).unwrap();
+        unsafe { *err = message.into_raw() };
+    }
+}
+
+// Unwinding across an `extern "C"` boundary aborts the caller, so a panic anywhere in the solver is
+// reported like any other failure instead.
+fn catch_panic<T>(err: *mut *mut c_char, f: impl FnOnce() -> *mut T) -> *mut T {
+    panic::catch_unwind(AssertUnwindSafe(f)).unwrap_or_else(|_| {
+        set_error(err, "internal error in the solver");
+        ptr::null_mut()
+    })
+}
+
+/// Parse a puzzle in the text format accepted by `Board::parse`.
+///
+/// Returns NULL on failure, in which case `*err` (if non-NULL) is set to a message which must be
+/// released with `hashi_string_free`.
+///
+/// # Safety
+///
+/// `puzzle` must be a valid NUL-terminated string, and `err` must be NULL or valid for writes.
+#[no_mangle]
+pub unsafe extern "C" fn hashi_parse(puzzle: *const c_char, err: *mut *mut c_char) -> *mut Board {
+    catch_panic(err, || {
+        if puzzle.is_null() {
+            set_error(err, "puzzle is NULL");
+            return ptr::null_mut();
+        }
+
+        let puzzle = match CStr::from_ptr(puzzle).to_str() {
+            Ok(puzzle) => puzzle,
+            Err(_) => {
+                set_error(err, "puzzle is not valid UTF-8");
+                return ptr::null_mut();
+            }
+        };
+
+        match Board::parse(puzzle) {
+            Ok(b) => Box::into_raw(Box::new(b)),
+            Err(e) => {
+                set_error(err, &e.to_string());
+                ptr::null_mut()
+            }
+        }
+    })
+}
+
+/// Solve a board returned by `hashi_parse`, returning the rendered solution.
+///
+/// Returns NULL on failure, in which case `*err` (if non-NULL) is set to a message. Both the
+/// solution and the error must be released with `hashi_string_free`.
+///
+/// # Safety
+///
+/// `board` must be a pointer returned by `hashi_parse` which has not yet been freed, and `err`
+/// must be NULL or valid for writes.
+#[no_mangle]
+pub unsafe extern "C" fn hashi_solve(
+    board: *const Board,
+    max_depth: usize,
+    max_visited: usize,
+    err: *mut *mut c_char,
+) -> *mut c_char {
+    catch_panic(err, || {
+        let board = match board.as_ref() {
+            Some(board) => board,
+            None => {
+                set_error(err, "board is NULL");
+                return ptr::null_mut();
+            }
+        };
+
+        match SolveState::new(board).solve(max_depth, max_visited) {
+            Ok((soln, _)) => CString::new(board.serialize_to_string(soln))
+                .unwrap()
+                .into_raw(),
+            Err(e) => {
+                set_error(err, e);
+                ptr::null_mut()
+            }
+        }
+    })
+}
+
+/// Release a board returned by `hashi_parse`. Passing NULL is a no-op.
+///
+/// # Safety
+///
+/// `board` must be NULL or a pointer returned by `hashi_parse` which has not yet been freed.
+#[no_mangle]
+pub unsafe extern "C" fn hashi_free(board: *mut Board) {
+    if !board.is_null() {
+        drop(Box::from_raw(board));
+    }
+}
+
+/// Release a string returned by this library. Passing NULL is a no-op.
+///
+/// # Safety
+///
+/// `s` must be NULL or a string returned by this library which has not yet been freed.
+#[no_mangle]
+pub unsafe extern "C" fn hashi_string_free(s: *mut c_char) {
+    if !s.is_null() {
+        drop(CString::from_raw(s));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_and_solve() {
+        let puzzle = CString::new("2 4 2").unwrap();
+        let mut err = ptr::null_mut();
+        unsafe {
+            let board = hashi_parse(puzzle.as_ptr(), &mut err);
+            assert!(!board.is_null());
+            assert!(err.is_null());
+
+            let soln = hashi_solve(board, 0, 0, &mut err);
+            assert!(!soln.is_null());
+            assert_eq!(CStr::from_ptr(soln).to_str().unwrap(), "2=4=2\n");
+
+            hashi_string_free(soln);
+            hashi_free(board);
+        }
+    }
+
+    #[test]
+    fn test_parse_error() {
+        let puzzle = CString::new("2 x").unwrap();
+        let mut err = ptr::null_mut();
+        unsafe {
+            let board = hashi_parse(puzzle.as_ptr(), &mut err);
+            assert!(board.is_null());
+            assert_eq!(
+                CStr::from_ptr(err).to_str().unwrap(),
+                "unexpected character (only expected 1-8 or ?) at (2, 0)"
+            );
+            hashi_string_free(err);
+        }
+    }
+
+    #[test]
+    fn test_panic_is_reported() {
+        let mut err = ptr::null_mut();
+        let result: *mut Board = catch_panic(&mut err, || panic!("oops"));
+        assert!(result.is_null());
+        unsafe {
+            assert_eq!(
+                CStr::from_ptr(err).to_str().unwrap(),
+                "internal error in the solver"
+            );
+            hashi_string_free(err);
+        }
+    }
+}
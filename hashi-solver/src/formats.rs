@@ -0,0 +1,784 @@
+//! Readers for the other formats puzzles get passed around in, besides the text grid read by
+//! `Board::parse`:
+//!
+//! - Simon Tatham's Bridges game IDs, e.g. `7x7m2:2a3c...`, where digits (and `A` onwards for 10
+//!   and up) are islands and each lowercase letter is a run of 1 to 26 empty cells.
+//! - Sparse lists of islands, one `x,y,clue` per line.
+//! - JSON, as `{"width": 7, "height": 7, "max_bridges": 2, "islands": [{"x": 0, "y": 0, "n": 3}]}`,
+//!   where the size and bridge count are optional, and `n` may also be a clue string like `"3+"`
+//!   or `null` for a wildcard. Each island may also be given as an `[x, y, n]` array. The
+//!   optional `id`, `title`, `author`, `source_url` and `difficulty` strings are kept as the
+//!   board's metadata, and `to_json` writes them back out.
+//!
+//! Boards can also be built cell by cell from what an image recognizer found in a picture of a
+//! puzzle, through a `CellDetector` given to `Board::from_cells`.
+//!
+//! Boards can also be written out as game IDs with `to_sgt`, and along with bridges as a save
+//! file for Simon Tatham's Bridges with `to_sgt_save`.
+//!
+//! Positions in errors are the column and line of the text, rather than a cell of the board.
+
+use alloc::collections::BTreeSet;
+use alloc::format;
+use alloc::string::String;
+use alloc::vec;
+use alloc::vec::Vec;
+use core::fmt::Write;
+
+use crate::{Board, BoardMetadata, BoardTopology, BoardVariant, ClueKind, Move, Node, ParseError};
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Format {
+    Grid,
+    SgtGameId,
+    Sparse,
+    Json,
+}
+
+// Guess the format of the text. Anything not recognizably in one of the other formats is taken
+// to be a grid.
+pub fn detect(s: &str) -> Format {
+    let t = s.trim();
+    if t.starts_with('{') {
+        return Format::Json;
+    }
+    if let Some((params, _)) = t.split_once(':') {
+        let mut size = params.splitn(2, 'x');
+        let width = size.next().unwrap_or("");
+        let height = size.next().unwrap_or("");
+        if !width.is_empty()
+            && width.bytes().all(|b| b.is_ascii_digit())
+            && height.starts_with(|c: char| c.is_ascii_digit())
+        {
+            return Format::SgtGameId;
+        }
+    }
+    // Grids never contain commas.
+    if t.contains(',') {
+        return Format::Sparse;
+    }
+    Format::Grid
+}
+
+pub fn parse(s: &str, format: Format) -> Result<Board, ParseError> {
+    match format {
+        Format::Grid => Board::parse(s),
+        Format::SgtGameId => parse_sgt(s),
+        Format::Sparse => parse_sparse(s),
+        Format::Json => parse_json(s),
+    }
+}
+
+fn error(message: &'static str, pos: (usize, usize)) -> ParseError {
+    ParseError { message, pos }
+}
+
+// A clue as written in a grid cell, e.g. `3`, `12`, `3+` or `?`.
+fn parse_clue(s: &str) -> Option<(Option<u8>, ClueKind)> {
+    if s == "?" {
+        return Some((None, ClueKind::Exact));
+    }
+    let (digits, kind) = match s.chars().last().and_then(ClueKind::from_suffix) {
+        Some(kind) => (&s[..s.len() - 1], kind),
+        None => (s, ClueKind::Exact),
+    };
+    if digits.is_empty() || !digits.bytes().all(|b| b.is_ascii_digit()) {
+        return None;
+    }
+    Some((Some(digits.parse().ok()?), kind))
+}
+
+// Build the board, checking for islands on top of each other. `pos` gives each island's
+// position in the text, for errors.
+fn build(
+    nodes: Vec<Node>,
+    text_pos: &[(usize, usize)],
+    size: Option<(usize, usize)>,
+    variant: BoardVariant,
+) -> Result<Board, ParseError> {
+    let mut seen = BTreeSet::new();
+    for (node, pos) in nodes.iter().zip(text_pos) {
+        if !seen.insert(node.pos) {
+            return Err(error("two islands at the same position", *pos));
+        }
+    }
+    if nodes.len() > Board::MAX_ISLANDS {
+        return Err(error(
+            "board has too many islands",
+            text_pos[Board::MAX_ISLANDS],
+        ));
+    }
+    let fits = |(w, h): (usize, usize)| nodes.iter().position(|n| n.pos.0 >= w || n.pos.1 >= h);
+    match size {
+        Some(size) => match fits(size) {
+            Some(idx) => Err(error("island outside the board", text_pos[idx])),
+            None => Ok(Board::new_sized(nodes, size, variant)),
+        },
+        None => Ok(Board::new_variant(nodes, variant)),
+    }
+}
+
+pub fn parse_sgt(s: &str) -> Result<Board, ParseError> {
+    let s = s.trim();
+    let (params, desc) = s
+        .split_once(':')
+        .ok_or(error("expected a game ID like 7x7:...", (0, 0)))?;
+    let (width, rest) = params
+        .split_once('x')
+        .ok_or(error("expected a size like 7x7", (0, 0)))?;
+    let digits = rest.bytes().take_while(|b| b.is_ascii_digit()).count();
+    let (height, mut rest) = rest.split_at(digits);
+    let size: (usize, usize) = match (width.parse(), height.parse()) {
+        (Ok(w), Ok(h)) if w > 0 && h > 0 => (w, h),
+        _ => return Err(error("expected a size like 7x7", (0, 0))),
+    };
+
+    // The other parameters are a letter and a number each; only the bridge count matters here.
+    let mut variant = BoardVariant::default();
+    while let Some(c) = rest.chars().next() {
+        let (_, value) = rest.split_at(c.len_utf8());
+        let digits = value.bytes().take_while(|b| b.is_ascii_digit()).count();
+        if c == 'm' {
+            variant.max_bridges = match value[..digits].parse() {
+                Ok(m @ 1..=3) => m,
+                _ => return Err(error("only 1 to 3 bridges are supported", (0, 0))),
+            };
+        }
+        rest = &value[digits..];
+    }
+
+    let offset = params.len() + 1;
+    let mut nodes = vec![];
+    let mut text_pos = vec![];
+    let mut cell = 0;
+    for (i, c) in desc.char_indices() {
+        match c {
+            'a'..='z' => cell += (c as usize) - ('a' as usize) + 1,
+            '1'..='9' | 'A'..='Z' => {
+                let n = match c {
+                    '1'..='9' => c as u8 - b'0',
+                    _ => c as u8 - b'A' + 10,
+                };
+                nodes.push(Node {
+                    n: Some(n),
+                    kind: ClueKind::Exact,
+                    pos: (cell % size.0, cell / size.0),
+                });
+                text_pos.push((offset + i, 0));
+                cell += 1;
+            }
+            _ => return Err(error("unexpected character in game ID", (offset + i, 0))),
+        }
+    }
+    if cell != size.0.saturating_mul(size.1) {
+        return Err(error(
+            "game ID does not fill the board",
+            (offset + desc.len(), 0),
+        ));
+    }
+    build(nodes, &text_pos, Some(size), variant)
+}
+
+pub fn parse_sparse(s: &str) -> Result<Board, ParseError> {
+    let mut nodes = vec![];
+    let mut text_pos = vec![];
+    for (y, line) in s.lines().enumerate() {
+        if line.trim().is_empty() {
+            continue;
+        }
+        let fields = line.split(',').map(str::trim).collect::<Vec<_>>();
+        let [x_str, y_str, clue] = fields[..] else {
+            return Err(error("expected x,y,clue", (0, y)));
+        };
+        let (Ok(x), Ok(node_y)) = (x_str.parse(), y_str.parse()) else {
+            return Err(error("expected a position", (0, y)));
+        };
+        let (n, kind) = parse_clue(clue).ok_or(error("expected a clue", (0, y)))?;
+        nodes.push(Node {
+            n,
+            kind,
+            pos: (x, node_y),
+        });
+        text_pos.push((0, y));
+    }
+    build(nodes, &text_pos, None, BoardVariant::default())
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Json {
+    Null,
+    Bool(bool),
+    Number(f64),
+    String(String),
+    Array(Vec<(usize, Json)>),
+    Object(Vec<(String, usize, Json)>),
+}
+
+// Just enough of a JSON reader for puzzles. Values are kept with their offset into the text.
+struct JsonReader<'a> {
+    s: &'a str,
+    at: usize,
+}
+
+impl JsonReader<'_> {
+    fn pos(&self, offset: usize) -> (usize, usize) {
+        let before = &self.s[..offset];
+        let line = before.matches('\n').count();
+        let col = before.len() - before.rfind('\n').map_or(0, |i| i + 1);
+        (col, line)
+    }
+
+    fn err<T>(&self, message: &'static str) -> Result<T, ParseError> {
+        Err(error(message, self.pos(self.at)))
+    }
+
+    fn skip_whitespace(&mut self) {
+        let rest = &self.s[self.at..];
+        self.at += rest.len() - rest.trim_start().len();
+    }
+
+    fn eat(&mut self, c: char) -> bool {
+        self.skip_whitespace();
+        if self.s[self.at..].starts_with(c) {
+            self.at += 1;
+            true
+        } else {
+            false
+        }
+    }
+
+    fn value(&mut self, depth: usize) -> Result<Json, ParseError> {
+        if depth > 32 {
+            return self.err("JSON is nested too deeply");
+        }
+        self.skip_whitespace();
+        let rest = &self.s[self.at..];
+        for (word, value) in [
+            ("null", Json::Null),
+            ("true", Json::Bool(true)),
+            ("false", Json::Bool(false)),
+        ] {
+            if rest.starts_with(word) {
+                self.at += word.len();
+                return Ok(value);
+            }
+        }
+        if self.eat('"') {
+            return self.string().map(Json::String);
+        }
+        if self.eat('[') {
+            let mut items = vec![];
+            if self.eat(']') {
+                return Ok(Json::Array(items));
+            }
+            loop {
+                self.skip_whitespace();
+                items.push((self.at, self.value(depth + 1)?));
+                if self.eat(']') {
+                    return Ok(Json::Array(items));
+                }
+                if !self.eat(',') {
+                    return self.err("expected , or ]");
+                }
+            }
+        }
+        if self.eat('{') {
+            let mut fields = vec![];
+            if self.eat('}') {
+                return Ok(Json::Object(fields));
+            }
+            loop {
+                if !self.eat('"') {
+                    return self.err("expected a key");
+                }
+                let key = self.string()?;
+                if !self.eat(':') {
+                    return self.err("expected :");
+                }
+                self.skip_whitespace();
+                fields.push((key, self.at, self.value(depth + 1)?));
+                if self.eat('}') {
+                    return Ok(Json::Object(fields));
+                }
+                if !self.eat(',') {
+                    return self.err("expected , or }");
+                }
+            }
+        }
+        let len = rest
+            .find(|c: char| !(c.is_ascii_digit() || "+-.eE".contains(c)))
+            .unwrap_or(rest.len());
+        match rest[..len].parse() {
+            Ok(n) if len > 0 => {
+                self.at += len;
+                Ok(Json::Number(n))
+            }
+            _ => self.err("expected a JSON value"),
+        }
+    }
+
+    // The rest of a string, after the opening quote.
+    fn string(&mut self) -> Result<String, ParseError> {
+        let mut out = String::new();
+        let mut chars = self.s[self.at..].char_indices();
+        while let Some((i, c)) = chars.next() {
+            match c {
+                '"' => {
+                    self.at += i + 1;
+                    return Ok(out);
+                }
+                '\\' => {
+                    let escaped = match chars.next().map(|(_, c)| c) {
+                        Some('n') => '\n',
+                        Some('t') => '\t',
+                        Some('r') => '\r',
+                        Some(c @ ('"' | '\\' | '/')) => c,
+                        Some('u') => {
+                            let hex = chars.by_ref().take(4).map(|(_, c)| c).collect::<String>();
+                            u32::from_str_radix(&hex, 16)
+                                .ok()
+                                .and_then(char::from_u32)
+                                .unwrap_or(char::REPLACEMENT_CHARACTER)
+                        }
+                        _ => return self.err("invalid escape in string"),
+                    };
+                    out.push(escaped);
+                }
+                c => out.push(c),
+            }
+        }
+        self.err("unterminated string")
+    }
+}
+
+pub fn parse_json(s: &str) -> Result<Board, ParseError> {
+    let mut reader = JsonReader { s, at: 0 };
+    let value = reader.value(0)?;
+    reader.skip_whitespace();
+    if reader.at != s.len() {
+        return reader.err("unexpected text after JSON");
+    }
+    let Json::Object(fields) = value else {
+        return Err(error("expected a JSON object", (0, 0)));
+    };
+
+    let number = |value: &Json, offset: usize| match value {
+        Json::Number(n) if *n >= 0.0 && *n <= u32::MAX as f64 && (*n as u32) as f64 == *n => {
+            Ok(*n as usize)
+        }
+        _ => Err(error("expected a whole number", reader.pos(offset))),
+    };
+    let clue = |value: &Json, offset: usize| match value {
+        Json::Null => Ok((None, ClueKind::Exact)),
+        Json::Number(_) => {
+            let n = number(value, offset)?;
+            let n = u8::try_from(n).map_err(|_| error("clue is too large", reader.pos(offset)))?;
+            Ok((Some(n), ClueKind::Exact))
+        }
+        Json::String(s) => parse_clue(s).ok_or(error("expected a clue", reader.pos(offset))),
+        _ => Err(error("expected a clue", reader.pos(offset))),
+    };
+
+    let (mut width, mut height) = (None, None);
+    let mut variant = BoardVariant::default();
+    let mut metadata = BoardMetadata::default();
+    let mut nodes = vec![];
+    let mut text_pos = vec![];
+    for (key, offset, value) in &fields {
+        match key.as_str() {
+            "width" => width = Some(number(value, *offset)?),
+            "height" => height = Some(number(value, *offset)?),
+            "max_bridges" => {
+                variant.max_bridges = match number(value, *offset)? {
+                    m @ 1..=3 => m as u8,
+                    _ => {
+                        let pos = reader.pos(*offset);
+                        return Err(error("only 1 to 3 bridges are supported", pos));
+                    }
+                }
+            }
+            "islands" => {
+                let Json::Array(islands) = value else {
+                    return Err(error("expected a list of islands", reader.pos(*offset)));
+                };
+                for (offset, island) in islands {
+                    let (x, y, n) = match island {
+                        Json::Array(items) => match &items[..] {
+                            [(ox, x), (oy, y), (on, n)] => {
+                                (number(x, *ox)?, number(y, *oy)?, clue(n, *on)?)
+                            }
+                            _ => return Err(error("expected [x, y, n]", reader.pos(*offset))),
+                        },
+                        Json::Object(island) => {
+                            let get = |name: &str| {
+                                island
+                                    .iter()
+                                    .find(|(key, _, _)| key == name)
+                                    .ok_or(error("island needs x, y and n", reader.pos(*offset)))
+                            };
+                            let ((_, ox, x), (_, oy, y)) = (get("x")?, get("y")?);
+                            let (_, on, n) = get("n")?;
+                            (number(x, *ox)?, number(y, *oy)?, clue(n, *on)?)
+                        }
+                        _ => return Err(error("expected an island", reader.pos(*offset))),
+                    };
+                    nodes.push(Node {
+                        n: n.0,
+                        kind: n.1,
+                        pos: (x, y),
+                    });
+                    text_pos.push(reader.pos(*offset));
+                }
+            }
+            "id" | "title" | "author" | "source_url" | "difficulty" => {
+                let Json::String(value) = value else {
+                    return Err(error("expected a string", reader.pos(*offset)));
+                };
+                let field = match key.as_str() {
+                    "id" => &mut metadata.id,
+                    "title" => &mut metadata.title,
+                    "author" => &mut metadata.author,
+                    "source_url" => &mut metadata.source_url,
+                    _ => &mut metadata.difficulty,
+                };
+                *field = Some(value.clone());
+            }
+            // Anything else is left for other tools.
+            _ => {}
+        }
+    }
+
+    let size = match (width, height) {
+        (Some(w), Some(h)) => Some((w, h)),
+        (None, None) => None,
+        _ => return Err(error("expected both width and height", (0, 0))),
+    };
+    Ok(build(nodes, &text_pos, size, variant)?.with_metadata(metadata))
+}
+
+// Write the board out as JSON in the format read by `parse_json`, along with its metadata.
+// Diagonal bridges and wrapping around a torus can't be written down in the format.
+pub fn to_json(board: &Board) -> String {
+    let mut s = String::new();
+    let (w, h) = board.size;
+    let m = board.variant.max_bridges;
+    write!(
+        s,
+        r#"{{"width": {}, "height": {}, "max_bridges": {}"#,
+        w, h, m
+    )
+    .unwrap();
+    let metadata = board.metadata();
+    for (key, value) in [
+        ("id", &metadata.id),
+        ("title", &metadata.title),
+        ("author", &metadata.author),
+        ("source_url", &metadata.source_url),
+        ("difficulty", &metadata.difficulty),
+    ] {
+        if let Some(value) = value {
+            write!(s, r#", "{}": "#, key).unwrap();
+            write_json_string(&mut s, value);
+        }
+    }
+    s.push_str(r#", "islands": ["#);
+    for (idx, node) in board.nodes.iter().enumerate() {
+        if idx > 0 {
+            s.push_str(", ");
+        }
+        let (x, y) = node.pos;
+        match (node.n, node.kind.suffix()) {
+            (None, _) => write!(s, "[{}, {}, null]", x, y),
+            (Some(n), None) => write!(s, "[{}, {}, {}]", x, y, n),
+            (Some(n), Some(suffix)) => write!(s, r#"[{}, {}, "{}{}"]"#, x, y, n, suffix),
+        }
+        .unwrap();
+    }
+    s.push_str("]}");
+    s
+}
+
+fn write_json_string(s: &mut String, value: &str) {
+    s.push('"');
+    for c in value.chars() {
+        match c {
+            '"' => s.push_str("\\\""),
+            '\\' => s.push_str("\\\\"),
+            '\n' => s.push_str("\\n"),
+            c if (c as u32) < 0x20 => write!(s, "\\u{:04x}", c as u32).unwrap(),
+            c => s.push(c),
+        }
+    }
+    s.push('"');
+}
+
+// What was recognized in a cell of a picture of a puzzle.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum DetectedCell {
+    Empty,
+    // An island with its clue, or `None` for a wildcard.
+    Island(Option<u8>),
+    // Something which couldn't be made out, which is an error rather than a guess.
+    Unrecognized,
+}
+
+// The hook for reading puzzles from images: an OCR step works out the grid of cells in the
+// picture and what's in each of them, and `Board::from_cells` builds the board from that.
+pub trait CellDetector {
+    // The number of columns and rows of cells.
+    fn size(&self) -> (usize, usize);
+    fn detect(&self, pos: (usize, usize)) -> DetectedCell;
+}
+
+// A grid of recognized characters, one per cell: a digit, or `A` onwards for 10 and up, for an
+// island, `?` for a wildcard island, and a space or `.` for an empty cell. Rows may be of
+// different lengths, with the missing cells empty.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CharMatrix(pub Vec<Vec<char>>);
+
+impl CellDetector for CharMatrix {
+    fn size(&self) -> (usize, usize) {
+        let width = self.0.iter().map(|row| row.len()).max().unwrap_or(0);
+        (width, self.0.len())
+    }
+
+    fn detect(&self, (x, y): (usize, usize)) -> DetectedCell {
+        match self.0[y].get(x).copied().unwrap_or(' ') {
+            ' ' | '.' => DetectedCell::Empty,
+            '?' => DetectedCell::Island(None),
+            c @ '1'..='9' => DetectedCell::Island(Some(c as u8 - b'0')),
+            c @ 'A'..='Z' => DetectedCell::Island(Some(c as u8 - b'A' + 10)),
+            _ => DetectedCell::Unrecognized,
+        }
+    }
+}
+
+pub(crate) fn from_cells(detector: &impl CellDetector) -> Result<Board, ParseError> {
+    let size = detector.size();
+    let mut nodes = vec![];
+    for y in 0..size.1 {
+        for x in 0..size.0 {
+            let n = match detector.detect((x, y)) {
+                DetectedCell::Empty => continue,
+                DetectedCell::Island(n) => n,
+                DetectedCell::Unrecognized => return Err(error("unrecognized cell", (x, y))),
+            };
+            nodes.push(Node {
+                n,
+                kind: ClueKind::Exact,
+                pos: (x, y),
+            });
+        }
+    }
+    let text_pos = nodes.iter().map(|n| n.pos).collect::<Vec<_>>();
+    build(nodes, &text_pos, Some(size), BoardVariant::default())
+}
+
+// The board's game ID for Simon Tatham's Bridges, as read by `parse_sgt`. Game IDs only hold
+// exact clues, and the game has no diagonal bridges or wrapping around a torus.
+pub fn to_sgt(board: &Board) -> Result<String, &'static str> {
+    if board.variant.diagonal || board.variant.topology != BoardTopology::Plane {
+        return Err("game IDs can't hold this variant");
+    }
+    let (w, h) = board.size;
+    let mut s = String::new();
+    write!(s, "{}x{}m{}:", w, h, board.variant.max_bridges).unwrap();
+    let mut empty = 0;
+    let mut cell = 0;
+    let flush = |s: &mut String, empty: &mut usize| {
+        while *empty > 0 {
+            let run = (*empty).min(26);
+            s.push((b'a' + run as u8 - 1) as char);
+            *empty -= run;
+        }
+    };
+    for node in board.nodes.iter() {
+        let (x, y) = node.pos;
+        empty += y * w + x - cell;
+        cell = y * w + x + 1;
+        let n = match (node.n, node.kind) {
+            (Some(n @ 1..=9), ClueKind::Exact) => b'0' + n,
+            (Some(n @ 10..=35), ClueKind::Exact) => b'A' + n - 10,
+            _ => return Err("game IDs can only hold clues from 1 to 35"),
+        };
+        flush(&mut s, &mut empty);
+        s.push(n as char);
+    }
+    empty += w * h - cell;
+    flush(&mut s, &mut empty);
+    Ok(s)
+}
+
+// A save file for Simon Tatham's Bridges with the board and the given bridges on it, e.g. a
+// solution or a game in progress, so that it can be opened in the game. Each pair of islands is
+// one move, so they can be undone one at a time.
+pub fn to_sgt_save(board: &Board, moves: &[Move]) -> Result<String, &'static str> {
+    let game_id = to_sgt(board)?;
+    let (_, desc) = game_id.split_once(':').unwrap();
+    // The full parameters also give how the board was generated, which doesn't matter here.
+    let (w, h) = board.size;
+    let params = format!("{}x{}i30e10m{}d0", w, h, board.variant.max_bridges);
+
+    let mut s = String::new();
+    let mut entry = |key: &str, value: &str| {
+        writeln!(s, "{:<8}:{}:{}", key, value.len(), value).unwrap();
+    };
+    entry("SAVEFILE", "Simon Tatham's Portable Puzzle Collection");
+    entry("VERSION", "1");
+    entry("GAME", "Bridges");
+    entry("PARAMS", &params);
+    entry("CPARAMS", &params);
+    entry("DESC", desc);
+    let states = format!("{}", moves.len() + 1);
+    entry("NSTATES", &states);
+    entry("STATEPOS", &states);
+    for mv in moves {
+        if board.edge_for_move(*mv).is_none() {
+            return Err("no bridge can connect these islands");
+        }
+        let ((x1, y1), (x2, y2)) = (mv.from, mv.to);
+        let line = format!("L{},{},{},{},{}", x1, y1, x2, y2, mv.count);
+        entry("MOVE", &line);
+    }
+    Ok(s)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_from_cells() {
+        let cells = ["2.4.2", "", "..?"];
+        let matrix = CharMatrix(cells.iter().map(|row| row.chars().collect()).collect());
+        let b = Board::from_cells(matrix).unwrap();
+        assert_eq!(b.size(), (5, 3));
+        assert_eq!(
+            b.canonical_id(),
+            Board::parse("2 4 2\n\n  ?").unwrap().canonical_id()
+        );
+
+        let smudged = CharMatrix(vec![vec!['2', ' ', '%']]);
+        assert_eq!(
+            Board::from_cells(smudged).map(|_| ()),
+            Err(error("unrecognized cell", (2, 0)))
+        );
+    }
+
+    #[test]
+    fn test_sgt_export() {
+        let b = Board::parse("2 4 2\n\n  2").unwrap();
+        let id = to_sgt(&b).unwrap();
+        assert_eq!(id, "5x3m2:2a4a2g2b");
+        assert_eq!(parse_sgt(&id).unwrap().canonical_id(), b.canonical_id());
+        assert_eq!(
+            to_sgt(&Board::parse("2 4 1+").unwrap()),
+            Err("game IDs can only hold clues from 1 to 35")
+        );
+
+        let moves = [
+            Move {
+                from: (0, 0),
+                to: (2, 0),
+                count: 1,
+            },
+            Move {
+                from: (2, 2),
+                to: (2, 0),
+                count: 2,
+            },
+        ];
+        let save = to_sgt_save(&b, &moves).unwrap();
+        let lines = save.lines().collect::<Vec<_>>();
+        assert_eq!(
+            lines[0],
+            "SAVEFILE:41:Simon Tatham's Portable Puzzle Collection"
+        );
+        assert_eq!(lines[3], "PARAMS  :13:5x3i30e10m2d0");
+        assert_eq!(lines[5], "DESC    :8:2a4a2g2b");
+        assert_eq!(lines[6..8], ["NSTATES :1:3", "STATEPOS:1:3"]);
+        assert_eq!(
+            lines[8..],
+            ["MOVE    :10:L0,0,2,0,1", "MOVE    :10:L2,2,2,0,2"]
+        );
+
+        let diagonal = Move {
+            from: (0, 0),
+            to: (2, 2),
+            count: 1,
+        };
+        assert!(to_sgt_save(&b, &[diagonal]).is_err());
+    }
+
+    #[test]
+    fn test_detect_and_parse() {
+        let grid = "2 4 2\n\n  1+\n";
+        let expected = Board::parse(grid).unwrap().canonical_id();
+        let inputs = [
+            (grid, Format::Grid),
+            ("5x3m2:2a4a2g1+", Format::SgtGameId),
+            ("0,0,2\n2,0,4\n4,0,2\n2,2,1+\n", Format::Sparse),
+            (
+                r#"{"islands": [[0, 0, 2], [2, 0, 4], {"x": 4, "y": 0, "n": 2}, [2, 2, "1+"]]}"#,
+                Format::Json,
+            ),
+        ];
+        for (input, format) in inputs {
+            assert_eq!(detect(input), format, "{}", input);
+            if format == Format::SgtGameId {
+                // Game IDs can't hold suffixed clues.
+                assert_eq!(
+                    Board::parse_any(input).map(|_| ()),
+                    Err(error("unexpected character in game ID", (13, 0)))
+                );
+                continue;
+            }
+            assert_eq!(Board::parse_any(input).unwrap().canonical_id(), expected);
+        }
+
+        let b = Board::parse_any("5x3m3i30e10:2a4a2g1b").unwrap();
+        assert_eq!(b.variant().max_bridges, 3);
+        assert_eq!(b.size(), (5, 3));
+        assert_eq!(b.to_puzzle_string(), "2 4 2\n\n  1\n");
+        assert_eq!(
+            parse_sgt("5x3:2a4a2f1f").map(|_| ()),
+            Err(error("game ID does not fill the board", (12, 0)))
+        );
+        assert_eq!(
+            parse_sparse("0,0,2\n0,0,3").map(|_| ()),
+            Err(error("two islands at the same position", (0, 1)))
+        );
+        assert_eq!(
+            parse_json("{\n  \"islands\": [[0, 0, 2]],\n  \"width\": 1\n}").map(|_| ()),
+            Err(error("expected both width and height", (0, 0)))
+        );
+        assert_eq!(
+            parse_json("{\"islands\": [[0, 0, 2.5]]}").map(|_| ()),
+            Err(error("expected a whole number", (20, 0)))
+        );
+    }
+
+    #[test]
+    fn test_json_metadata() {
+        let input = r#"{"title": "Bridges \"1\"", "author": "A. Setter", "difficulty": "hard",
+            "islands": [[0, 0, 2], [2, 0, 4], [4, 0, 2], [2, 2, "1+"], [4, 2, null]]}"#;
+        let b = parse_json(input).unwrap();
+        assert_eq!(
+            *b.metadata(),
+            BoardMetadata {
+                title: Some("Bridges \"1\"".into()),
+                author: Some("A. Setter".into()),
+                difficulty: Some("hard".into()),
+                ..Default::default()
+            }
+        );
+        let round_trip = parse_json(&to_json(&b)).unwrap();
+        assert_eq!(round_trip.metadata(), b.metadata());
+        assert_eq!(round_trip.canonical_id(), b.canonical_id());
+        assert_eq!(to_json(&round_trip), to_json(&b));
+        assert_eq!(
+            parse_json(r#"{"id": 3, "islands": []}"#).map(|_| ()),
+            Err(error("expected a string", (7, 0)))
+        );
+    }
+}
@@ -0,0 +1,636 @@
+//! Puzzles generated from a seed. `random_puzzle` builds a solvable board by growing bridges out
+//! from an island; `generate` keeps generating candidate boards that way until one is as hard as
+//! asked for and has a single solution, and `generate_with` until the caller's own filter accepts
+//! one too. With the `parallel` feature,
+//! `generate_parallel` and `generate_parallel_with` spread the work across the rayon thread pool.
+//! `daily` gives the puzzle of the day: its seed comes from the date alone, so every client
+//! generates the same puzzle offline without asking a server for it. `UniquenessCheck` checks a
+//! series of boards which differ by an island or so, as when tweaking a candidate, without
+//! starting from scratch each time. Enabled with the `generator` feature.
+
+use alloc::collections::{BTreeMap, BTreeSet};
+use alloc::vec;
+use alloc::vec::Vec;
+use core::fmt;
+
+#[cfg(feature = "parallel")]
+use rayon::prelude::*;
+
+use crate::analyze::{difficulty_report, estimate_hardness, DifficultyReport};
+use crate::rng::Rng;
+use crate::{Board, BoardVariant, ClueKind, Move, Node, Solution, SolveOptions, SolveState};
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct GenerateOptions {
+    pub size: (usize, usize),
+    // The most islands to place; see `random_puzzle`.
+    pub islands: usize,
+    // The fraction of cells to place islands on, instead of `islands`.
+    pub density: Option<f64>,
+    pub variant: BoardVariant,
+    // How the bridges are laid out; see `random_puzzle_shaped`.
+    pub shape: Shape,
+    // The range of hardness scores to accept (see `HardnessEstimate::score`).
+    pub min_hardness: f64,
+    pub max_hardness: f64,
+    // Only accept boards with a single solution.
+    pub unique: bool,
+    // How many candidates to try before giving up.
+    pub max_candidates: usize,
+    // How many states the uniqueness check may visit for each candidate. Candidates it can't
+    // settle within this are rejected.
+    pub max_visited: usize,
+}
+
+impl Default for GenerateOptions {
+    fn default() -> Self {
+        GenerateOptions {
+            size: (7, 7),
+            islands: 12,
+            density: None,
+            variant: BoardVariant::default(),
+            shape: Shape::default(),
+            min_hardness: 0.0,
+            max_hardness: f64::INFINITY,
+            unique: true,
+            max_candidates: 1_000,
+            max_visited: 100_000,
+        }
+    }
+}
+
+// The first of the candidates generated from `seed` which meets `options`, along with the
+// solution it was built from. `None` if none of `options.max_candidates` do.
+pub fn generate(seed: u64, options: &GenerateOptions) -> Option<(Board, Solution)> {
+    (0..options.max_candidates).find_map(|i| candidate(seed, i, options, None).ok())
+}
+
+// Like `generate`, only accepting boards which `filter` also accepts, going by the board and its
+// `analyze::difficulty_report`, e.g. to rule out islands with an 8 or to insist on a technique.
+// Boards the report can't be made for within `options.max_visited` are rejected.
+pub fn generate_with(
+    seed: u64,
+    options: &GenerateOptions,
+    filter: impl Fn(&Board, &DifficultyReport) -> bool,
+) -> Option<(Board, Solution)> {
+    (0..options.max_candidates).find_map(|i| candidate(seed, i, options, Some(&filter)).ok())
+}
+
+// Like `generate_with`, also counting why each candidate before the one returned was turned
+// down, for seeing what's holding back the yield when tuning `options`.
+pub fn generate_with_rejections(
+    seed: u64,
+    options: &GenerateOptions,
+    filter: impl Fn(&Board, &DifficultyReport) -> bool,
+    rejections: &mut Rejections,
+) -> Option<(Board, Solution)> {
+    (0..options.max_candidates).find_map(|i| {
+        candidate(seed, i, options, Some(&filter))
+            .map_err(|r| rejections.record(r))
+            .ok()
+    })
+}
+
+// Like `generate`, with the candidates generated, rated and checked for uniqueness in parallel.
+// It gives the same board as `generate` for the same seed.
+#[cfg(feature = "parallel")]
+pub fn generate_parallel(seed: u64, options: &GenerateOptions) -> Option<(Board, Solution)> {
+    (0..options.max_candidates)
+        .into_par_iter()
+        .find_map_first(|i| candidate(seed, i, options, None).ok())
+}
+
+// `generate_with`, in parallel as `generate_parallel` is.
+#[cfg(feature = "parallel")]
+pub fn generate_parallel_with(
+    seed: u64,
+    options: &GenerateOptions,
+    filter: impl Fn(&Board, &DifficultyReport) -> bool + Sync,
+) -> Option<(Board, Solution)> {
+    (0..options.max_candidates)
+        .into_par_iter()
+        .find_map_first(|i| candidate(seed, i, options, Some(&filter)).ok())
+}
+
+type Filter<'a> = &'a dyn Fn(&Board, &DifficultyReport) -> bool;
+
+// Why a candidate board was turned down.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Rejection {
+    TooEasy,
+    TooHard,
+    // It has more than one solution.
+    Ambiguous,
+    // Checking it for uniqueness or rating it for the filter gave up within `max_visited`.
+    Undecided,
+    Filtered,
+}
+
+impl Rejection {
+    pub fn reason(self) -> &'static str {
+        match self {
+            Rejection::TooEasy => "too easy",
+            Rejection::TooHard => "too hard",
+            Rejection::Ambiguous => "ambiguous",
+            Rejection::Undecided => "undecided",
+            Rejection::Filtered => "failed filter",
+        }
+    }
+}
+
+// How many candidates were turned down for each `Rejection`.
+#[derive(Debug, Copy, Clone, Default, PartialEq, Eq)]
+pub struct Rejections {
+    pub too_easy: usize,
+    pub too_hard: usize,
+    pub ambiguous: usize,
+    pub undecided: usize,
+    pub filtered: usize,
+}
+
+impl Rejections {
+    pub fn record(&mut self, rejection: Rejection) {
+        let count = match rejection {
+            Rejection::TooEasy => &mut self.too_easy,
+            Rejection::TooHard => &mut self.too_hard,
+            Rejection::Ambiguous => &mut self.ambiguous,
+            Rejection::Undecided => &mut self.undecided,
+            Rejection::Filtered => &mut self.filtered,
+        };
+        *count += 1;
+    }
+
+    pub fn count(&self, rejection: Rejection) -> usize {
+        match rejection {
+            Rejection::TooEasy => self.too_easy,
+            Rejection::TooHard => self.too_hard,
+            Rejection::Ambiguous => self.ambiguous,
+            Rejection::Undecided => self.undecided,
+            Rejection::Filtered => self.filtered,
+        }
+    }
+
+    pub fn total(&self) -> usize {
+        self.too_easy + self.too_hard + self.ambiguous + self.undecided + self.filtered
+    }
+}
+
+impl fmt::Display for Rejections {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} rejected", self.total())?;
+        let all = [
+            Rejection::TooEasy,
+            Rejection::TooHard,
+            Rejection::Ambiguous,
+            Rejection::Undecided,
+            Rejection::Filtered,
+        ];
+        let mut sep = ": ";
+        for r in all.into_iter().filter(|r| self.count(*r) > 0) {
+            write!(f, "{}{} {}", sep, self.count(r), r.reason())?;
+            sep = ", ";
+        }
+        Ok(())
+    }
+}
+
+// The `i`th candidate from `seed`, if it meets `options` and `filter`. The cheap rating comes
+// first, so that only boards which pass it are checked for uniqueness, and only unique boards
+// are solved for the filter's report.
+fn candidate(
+    seed: u64,
+    i: usize,
+    options: &GenerateOptions,
+    filter: Option<Filter>,
+) -> Result<(Board, Solution), Rejection> {
+    let seed = Rng(seed.wrapping_add(i as u64)).next();
+    let (w, h) = options.size;
+    let islands = match options.density {
+        Some(density) => ((w * h) as f64 * density) as usize,
+        None => options.islands,
+    };
+    let (board, solution) =
+        random_puzzle_shaped(seed, options.size, islands, options.variant, &options.shape);
+    // Without a bridge, there's nothing to solve.
+    if board.nodes.len() < 2 {
+        return Err(Rejection::TooEasy);
+    }
+    let hardness = estimate_hardness(&board).score();
+    if hardness < options.min_hardness {
+        return Err(Rejection::TooEasy);
+    }
+    if hardness > options.max_hardness {
+        return Err(Rejection::TooHard);
+    }
+    if options.unique {
+        let solutions = SolveState::new(&board)
+            .distinct_solutions(2, options.max_visited, false)
+            .map_err(|_| Rejection::Undecided)?;
+        if solutions.len() != 1 {
+            return Err(Rejection::Ambiguous);
+        }
+    }
+    if let Some(filter) = filter {
+        let solve_options = SolveOptions {
+            max_visited: options.max_visited,
+            ..Default::default()
+        };
+        let report = difficulty_report(&board, &solve_options).map_err(|_| Rejection::Undecided)?;
+        if !filter(&board, &report) {
+            return Err(Rejection::Filtered);
+        }
+    }
+    Ok((board, solution))
+}
+
+// Checks boards for a single solution one after another, reusing what it found out about the
+// previous board: its solutions carry over wherever the next board's clues still agree with them,
+// so two of them show it's ambiguous without a search, and its deductions are placed again as
+// long as they're still forced.
+#[derive(Debug, Clone, Default)]
+pub struct UniquenessCheck {
+    max_visited: usize,
+    previous: Option<Board>,
+    // The previous board's solutions, as moves so they can be looked for on the next board.
+    solutions: Vec<Vec<Move>>,
+    // The bridges deduced on the previous board before anything had to be tried, with reasons.
+    deductions: (Vec<usize>, Vec<&'static str>),
+    searches_skipped: usize,
+}
+
+impl UniquenessCheck {
+    // `max_visited` is how many states each search may visit.
+    pub fn new(max_visited: usize) -> Self {
+        UniquenessCheck {
+            max_visited,
+            ..Default::default()
+        }
+    }
+
+    // Whether the board has exactly one solution. Fails if the search gives up first.
+    pub fn is_unique(&mut self, board: &Board) -> Result<bool, &'static str> {
+        let mut carried: Vec<Solution> = vec![];
+        for moves in &self.solutions {
+            let Ok(mut soln) = Solution::from_moves(board, moves) else {
+                continue;
+            };
+            soln.edges.sort();
+            if soln.verify(board).is_ok() && !carried.contains(&soln) {
+                carried.push(soln);
+            }
+        }
+        if carried.len() > 1 {
+            self.searches_skipped += 1;
+            self.remember(board, carried, None);
+            return Ok(false);
+        }
+
+        let mut state = SolveState::new(board);
+        if let Some(previous) = &self.previous {
+            let (soln, log) = &self.deductions;
+            state.reuse_deductions(previous, soln, log);
+        }
+        while let Some((edge, reason)) = state.solve_fully_constrained() {
+            state.add_edge(edge, reason);
+        }
+        let solutions = state.distinct_solutions(2, self.max_visited, false)?;
+        let unique = solutions.len() == 1;
+        self.remember(board, solutions, Some((state.soln, state.log)));
+        Ok(unique)
+    }
+
+    // How many boards were found to be ambiguous from the previous board's solutions alone.
+    pub fn searches_skipped(&self) -> usize {
+        self.searches_skipped
+    }
+
+    fn remember(
+        &mut self,
+        board: &Board,
+        solutions: Vec<Solution>,
+        deductions: Option<(Vec<usize>, Vec<&'static str>)>,
+    ) {
+        self.solutions = solutions.iter().map(|s| s.moves(board)).collect();
+        // Deductions only carry over from the board they were made on.
+        self.deductions = deductions.unwrap_or_default();
+        self.previous = Some(board.clone());
+    }
+}
+
+// How many candidate boards `daily` rates before settling for the closest to the difficulty.
+const ATTEMPTS: usize = 32;
+// How close a candidate's hardness score must be to the difficulty to be taken straight away.
+const TOLERANCE: f64 = 0.25;
+
+// The seed of the puzzle of the day, for `date` as given, e.g. "2024-05-01", on a board of `size`
+// with `difficulty` as a hardness score (see `HardnessEstimate::score`). The date is hashed as it
+// is, so clients must agree on how they write it.
+pub fn daily_seed(date: &str, size: (usize, usize), difficulty: f64) -> u64 {
+    // FNV-1a over the date, then each parameter is folded in through the generator.
+    let mut hash = 0xcbf2_9ce4_8422_2325u64;
+    for b in date.trim().bytes() {
+        hash = (hash ^ b as u64).wrapping_mul(0x0100_0000_01b3);
+    }
+    for n in [size.0 as u64, size.1 as u64, difficulty.to_bits()] {
+        hash = Rng(hash ^ n).next();
+    }
+    hash
+}
+
+// The puzzle of the day and the solution it was built from. Candidates are generated from seeds
+// following `daily_seed` until one is rated close to `difficulty`; failing that, the closest one is
+// used.
+pub fn daily(date: &str, size: (usize, usize), difficulty: f64) -> (Board, Solution) {
+    let mut rng = Rng(daily_seed(date, size, difficulty));
+    let islands = (size.0 * size.1 / 4).max(2);
+    let mut best: Option<(f64, (Board, Solution))> = None;
+    for _ in 0..ATTEMPTS {
+        let candidate = random_puzzle(rng.next(), size, islands, BoardVariant::default());
+        let score = estimate_hardness(&candidate.0).score();
+        let distance = if score > difficulty {
+            score - difficulty
+        } else {
+            difficulty - score
+        };
+        if distance <= TOLERANCE {
+            return candidate;
+        }
+        if best.as_ref().is_none_or(|(d, _)| distance < *d) {
+            best = Some((distance, candidate));
+        }
+    }
+    best.expect("at least one candidate is generated").1
+}
+
+// How `random_puzzle_shaped` lays out its bridges, for generating boards of different character.
+// Each choice left as `None` is made uniformly at random, as `random_puzzle` does.
+#[derive(Debug, Copy, Clone, Default, PartialEq)]
+pub struct Shape {
+    // The chance of each pair of islands being joined by more than one bridge.
+    pub double_bridges: Option<f64>,
+    // The chance of each bridge being drawn from the longer half of the lengths it could have.
+    pub long_bridges: Option<f64>,
+    // The chance of growing the next bridge from the island with the most bridges so far, rather
+    // than any island, which gives more islands with high clues.
+    pub high_clues: Option<f64>,
+}
+
+// A solvable board of up to `islands` islands, built by growing bridges out from a random island,
+// along with the solution it was built from. The board may have other solutions as well.
+pub fn random_puzzle(
+    seed: u64,
+    size: (usize, usize),
+    islands: usize,
+    variant: BoardVariant,
+) -> (Board, Solution) {
+    random_puzzle_shaped(seed, size, islands, variant, &Shape::default())
+}
+
+// Like `random_puzzle`, with the bridges laid out as `shape` asks.
+pub fn random_puzzle_shaped(
+    seed: u64,
+    size: (usize, usize),
+    islands: usize,
+    variant: BoardVariant,
+    shape: &Shape,
+) -> (Board, Solution) {
+    // Bridges need an empty cell between the islands they join, so there's no room for any.
+    if size.0.min(size.1) == 0 || size.0.max(size.1) < 3 {
+        return (
+            Board::new_sized(vec![], size, variant),
+            Solution { edges: vec![] },
+        );
+    }
+
+    let mut rng = Rng(seed);
+    let start = (rng.range(0, size.0 - 1), rng.range(0, size.1 - 1));
+    let mut clues = BTreeMap::from([(start, 0)]);
+    let mut used = BTreeSet::new();
+    let mut moves = vec![];
+
+    for _ in 0..islands * 100 {
+        if clues.len() >= islands {
+            break;
+        }
+        let from = match shape.high_clues {
+            Some(p) if rng.chance(p) => *clues.iter().max_by_key(|(_, n)| **n).unwrap().0,
+            _ => *clues.keys().nth(rng.range(0, clues.len() - 1)).unwrap(),
+        };
+        let (dx, dy) = [(1, 0), (-1, 0), (0, 1), (0, -1)][rng.range(0, 3)];
+        let max_len = size.0.max(size.1) / 2 + 1;
+        let len = match shape.long_bridges {
+            Some(p) if rng.chance(p) => rng.range((2 + max_len).div_ceil(2), max_len),
+            Some(_) => rng.range(2, (2 + max_len) / 2),
+            None => rng.range(2, max_len),
+        } as isize;
+
+        // The bridge may only cross empty cells, and mustn't end up on an existing bridge.
+        let cells = (1..=len)
+            .map(|i| {
+                let x = from.0 as isize + dx * i;
+                let y = from.1 as isize + dy * i;
+                (x >= 0 && y >= 0 && (x as usize) < size.0 && (y as usize) < size.1)
+                    .then_some((x as usize, y as usize))
+            })
+            .collect::<Option<Vec<_>>>();
+        let Some(cells) = cells else {
+            continue;
+        };
+        if cells
+            .iter()
+            .any(|c| clues.contains_key(c) || used.contains(c))
+        {
+            continue;
+        }
+
+        let to = *cells.last().unwrap();
+        let max = variant.max_bridges as usize;
+        let count = match shape.double_bridges {
+            Some(p) if max > 1 && rng.chance(p) => rng.range(2, max),
+            Some(_) => 1,
+            None => rng.range(1, max),
+        } as u8;
+        used.extend(cells[..cells.len() - 1].iter().copied());
+        *clues.get_mut(&from).unwrap() += count;
+        clues.insert(to, count);
+        moves.push(Move { from, to, count });
+    }
+
+    let nodes = clues
+        .into_iter()
+        .map(|(pos, n)| Node {
+            n: Some(n),
+            kind: ClueKind::Exact,
+            pos,
+        })
+        .collect();
+    let board = Board::new_sized(nodes, size, variant);
+    let solution =
+        Solution::from_moves(&board, &moves).expect("generated bridges run along board edges");
+    (board, solution)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_generate() {
+        let options = GenerateOptions {
+            min_hardness: 0.5,
+            ..Default::default()
+        };
+        let (b, mut solution) = generate(7, &options).unwrap();
+        assert!(estimate_hardness(&b).score() >= 0.5);
+        solution.edges.sort();
+        let solutions = SolveState::new(&b)
+            .distinct_solutions(2, options.max_visited, false)
+            .unwrap();
+        assert_eq!(solutions, vec![solution]);
+
+        #[cfg(feature = "parallel")]
+        assert_eq!(
+            generate_parallel(7, &options).unwrap().0.to_puzzle_string(),
+            b.to_puzzle_string()
+        );
+
+        let dense = GenerateOptions {
+            size: (10, 10),
+            density: Some(0.3),
+            shape: Shape {
+                double_bridges: Some(0.0),
+                ..Default::default()
+            },
+            unique: false,
+            ..Default::default()
+        };
+        let (b, solution) = generate(7, &dense).unwrap();
+        // More than the 12 islands `islands` would have given.
+        assert!(b.nodes.len() > 12);
+        assert!(solution.moves(&b).iter().all(|mv| mv.count == 1));
+
+        // Too small for a bridge, or for anything at all.
+        for size in [(0, 0), (0, 5), (5, 0), (1, 1), (2, 2)] {
+            let tiny = GenerateOptions {
+                size,
+                islands: 2,
+                ..Default::default()
+            };
+            assert!(generate(7, &tiny).is_none());
+            let (b, _) = random_puzzle(7, size, 2, BoardVariant::default());
+            assert!(b.nodes.is_empty());
+        }
+        let narrow = GenerateOptions {
+            size: (1, 5),
+            islands: 2,
+            ..Default::default()
+        };
+        assert!(generate(7, &narrow).is_some());
+
+        // No island may take more than 4 bridges, and guesses mustn't be stacked.
+        let filter = |b: &Board, report: &DifficultyReport| {
+            b.nodes.iter().all(|n| n.n.is_some_and(|n| n <= 4)) && report.deepest_guess <= 1
+        };
+        let (b, _) = generate_with(7, &options, filter).unwrap();
+        assert!(filter(
+            &b,
+            &difficulty_report(&b, &SolveOptions::default()).unwrap()
+        ));
+        #[cfg(feature = "parallel")]
+        assert_eq!(
+            generate_parallel_with(7, &options, filter)
+                .unwrap()
+                .0
+                .to_puzzle_string(),
+            b.to_puzzle_string()
+        );
+        assert!(generate_with(7, &options, |_, _| false).is_none());
+
+        let mut rejections = Rejections::default();
+        let (found, _) = generate_with_rejections(7, &options, filter, &mut rejections).unwrap();
+        assert_eq!(found.to_puzzle_string(), b.to_puzzle_string());
+        assert!(rejections.too_easy > 0);
+        assert_eq!(rejections.too_hard, 0);
+        assert!(rejections.to_string().contains(" too easy"));
+
+        let mut rejections = Rejections::default();
+        let strict = GenerateOptions {
+            max_candidates: 20,
+            ..options
+        };
+        assert!(generate_with_rejections(7, &strict, |_, _| false, &mut rejections).is_none());
+        assert_eq!(rejections.total(), 20);
+        assert_eq!(rejections.count(Rejection::TooEasy), rejections.too_easy);
+
+        let impossible = GenerateOptions {
+            min_hardness: f64::INFINITY,
+            max_candidates: 10,
+            ..options
+        };
+        assert!(generate(7, &impossible).is_none());
+    }
+
+    #[test]
+    fn test_uniqueness_check() {
+        let square = Board::parse("3 3\n\n3 3").unwrap();
+        let mut check = UniquenessCheck::new(10_000);
+        assert_eq!(check.is_unique(&square), Ok(false));
+        assert_eq!(check.searches_skipped(), 0);
+        // Both solutions are still there.
+        assert_eq!(check.is_unique(&square), Ok(false));
+        assert_eq!(check.searches_skipped(), 1);
+
+        let tweaked = Board::parse("2 3\n\n3 4").unwrap();
+        assert_eq!(check.is_unique(&tweaked), Ok(true));
+        assert_eq!(check.is_unique(&square), Ok(false));
+        assert_eq!(check.searches_skipped(), 1);
+    }
+
+    #[test]
+    fn test_daily() {
+        let (b, solution) = daily("2024-05-01", (7, 7), 0.0);
+        let (again, _) = daily(" 2024-05-01\n", (7, 7), 0.0);
+        assert_eq!(b.to_puzzle_string(), again.to_puzzle_string());
+        assert_eq!(solution.verify(&b), Ok(()));
+
+        let (other, _) = daily("2024-05-02", (7, 7), 0.0);
+        assert_ne!(b.to_puzzle_string(), other.to_puzzle_string());
+        assert_ne!(
+            daily_seed("2024-05-01", (7, 7), 0.0),
+            daily_seed("2024-05-01", (7, 7), 1.0)
+        );
+    }
+
+    #[test]
+    fn test_random_puzzle_shaped() {
+        let size = (12, 12);
+        let count = |shape: &Shape, f: &dyn Fn(&Move) -> bool| {
+            (0..20)
+                .map(|seed| {
+                    let (b, soln) =
+                        random_puzzle_shaped(seed, size, 30, BoardVariant::default(), shape);
+                    soln.moves(&b).iter().filter(|mv| f(mv)).count()
+                })
+                .sum::<usize>()
+        };
+        let doubles = |mv: &Move| mv.count > 1;
+        let never = Shape {
+            double_bridges: Some(0.0),
+            ..Default::default()
+        };
+        let always = Shape {
+            double_bridges: Some(1.0),
+            ..Default::default()
+        };
+        assert_eq!(count(&never, &doubles), 0);
+        assert!(count(&always, &doubles) > count(&Shape::default(), &doubles));
+
+        // Bridges on a 12x12 board are 2 to 7 long; long ones are at least 5.
+        let short = |mv: &Move| mv.from.0.abs_diff(mv.to.0) + mv.from.1.abs_diff(mv.to.1) < 5;
+        let long = Shape {
+            long_bridges: Some(1.0),
+            ..Default::default()
+        };
+        assert_eq!(count(&long, &short), 0);
+    }
+}
@@ -0,0 +1,325 @@
+//! Sample boards with their solutions, for demos and benchmarks. Enabled with the `puzzles`
+//! feature.
+
+use crate::Board;
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Difficulty {
+    // Solvable by deduction alone.
+    Easy,
+    // Needs a little speculation.
+    Medium,
+    // Large boards with long chains of deductions.
+    Hard,
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct Puzzle {
+    pub name: &'static str,
+    pub difficulty: Difficulty,
+    // The clues, in the format accepted by `Board::parse`.
+    pub puzzle: &'static str,
+    // The solution, as rendered by `Board::serialize_styled_to_string` in compact mode.
+    pub solution: &'static str,
+}
+
+impl Puzzle {
+    pub fn board(&self) -> Board {
+        Board::parse(self.puzzle).unwrap()
+    }
+}
+
+pub const PUZZLES: &[Puzzle] = &[
+    Puzzle {
+        name: "tiny_5x5",
+        difficulty: Difficulty::Easy,
+        puzzle: TINY_5X5,
+        solution: TINY_5X5_SOLN,
+    },
+    Puzzle {
+        name: "easy_7x7",
+        difficulty: Difficulty::Easy,
+        puzzle: EASY_7X7,
+        solution: EASY_7X7_SOLN,
+    },
+    Puzzle {
+        name: "easy_10x10",
+        difficulty: Difficulty::Easy,
+        puzzle: EASY_10X10,
+        solution: EASY_10X10_SOLN,
+    },
+    Puzzle {
+        name: "medium_10x10",
+        difficulty: Difficulty::Medium,
+        puzzle: MEDIUM_10X10,
+        solution: MEDIUM_10X10_SOLN,
+    },
+    Puzzle {
+        name: "medium_15x15",
+        difficulty: Difficulty::Medium,
+        puzzle: MEDIUM_15X15,
+        solution: MEDIUM_15X15_SOLN,
+    },
+    Puzzle {
+        name: "hard_25x25",
+        difficulty: Difficulty::Hard,
+        puzzle: HARD_25X25,
+        solution: HARD_25X25_SOLN,
+    },
+    Puzzle {
+        name: "hard_25x25_2",
+        difficulty: Difficulty::Hard,
+        puzzle: HARD_25X25_2,
+        solution: HARD_25X25_2_SOLN,
+    },
+];
+
+pub fn by_difficulty(difficulty: Difficulty) -> impl Iterator<Item = &'static Puzzle> {
+    PUZZLES.iter().filter(move |p| p.difficulty == difficulty)
+}
+
+pub fn by_name(name: &str) -> Option<&'static Puzzle> {
+    PUZZLES.iter().find(|p| p.name == name)
+}
+
+const TINY_5X5: &str = r#"
+4 5 3
+
+2 1 1
+"#;
+const TINY_5X5_SOLN: &str = r#"
+4=5=3
+‖ | |
+2 1 1
+"#;
+
+const EASY_7X7: &str = r#"
+ 2    4
+3  4 3
+
+ 1 2  3
+4    3
+
+3  3  3
+"#;
+const EASY_7X7_SOLN: &str = r#"
+ 2====4
+3==4-3‖
+|  | ‖‖
+|1-2 ‖3
+4----3|
+‖     |
+3--3==3
+"#;
+
+const EASY_10X10: &str = r#"
+2
+ 2  4    3
+      3 4
+3 3 5
+        2
+       1 4
+ 1    4 2
+    2  1 2
+2 4   3 1
+"#;
+const EASY_10X10_SOLN: &str = r#"
+2         
+‖2--4----3
+‖|  ‖ 3=4‖
+3|3-5 | ‖‖
+||‖ ‖ | 2‖
+||‖ ‖ |1-4
+|1‖ ‖ 4=2|
+| ‖ 2 |1-2
+2-4---3-1 
+"#;
+
+const MEDIUM_10X10: &str = r#"
+ 3   3 4 3
+   3    1
+
+ 3  2 3 2
+         2
+ 3 6    3
+    2 4
+   4 2
+         1
+ 2    3 1
+"#;
+const MEDIUM_10X10_SOLN: &str = r#"
+ 3---3=4=3
+ ‖ 3----1|
+ ‖ ‖     |
+ 3 ‖2=3-2|
+ | ‖    |2
+ 3-6----3|
+ | ‖2=4 ||
+ | 4=2‖ ||
+ |    ‖ |1
+ 2----3 1 
+"#;
+
+const MEDIUM_15X15: &str = r#"
+ 3    2 1   2
+   3   4  2   1
+      2  3  4
+1
+ 4 3 4      5
+3     3  1    3
+   1    1
+
+        2   4 6
+ 2
+5  4  4    4  6
+     3      1
+1
+ 1 4 4    3 2 2
+"#;
+const MEDIUM_15X15_SOLN: &str = r#"
+ 3====2 1---2  
+ | 3===4==2 | 1
+ | |  2==3--4 |
+1| |        ‖ |
+|4-3-4======5 |
+3‖   |3--1  | 3
+‖‖ 1 |‖ 1   | ‖
+‖‖ | |‖ |   | ‖
+‖‖ | |‖ 2---4=6
+‖2 | |‖       ‖
+5==4 |4====4==6
+|  | 3------1 ‖
+1  | |        ‖
+ 1-4=4----3=2 2
+"#;
+
+const HARD_25X25: &str = r#"
+3 4             5 2 1  1
+    3       2           1
+     2 3        6   4  4
+                  3   3 3
+2  1  3        2 2 1
+                  1
+                 5 4 1
+1                   2 4
+
+                       4
+3
+                   2 1
+                 6    5
+                  2  2
+3
+                  5  5 4
+    2 4         5
+                 3
+   2            3    1 2
+                 1
+5 5               6   7 6
+   2       4
+4      4  1
+
+2 1 1  5   5      4   2 2
+"#;
+const HARD_25X25_SOLN: &str = r#"
+3-4-------------5=2 1  1 
+‖ ‖ 3=======2   ‖   |  |1
+‖ ‖ |2=3--------6===4--4|
+‖ ‖ |           | 3===3‖3
+2 ‖1| 3========2|2|1  |‖‖
+  ‖|| |         |‖1|  |‖‖
+  ‖|| |         |5-4-1|‖‖
+1 ‖|| |         |‖ |2=4‖‖
+| ‖|| |         |‖ |  |‖‖
+| ‖|| |         |‖ |  |4‖
+3 ‖|| |         |‖ |  |‖‖
+‖ ‖|| |         |‖ 2-1|‖‖
+‖ ‖|| |         |6====5‖‖
+‖ ‖|| |         |‖2  2‖‖‖
+3 ‖|| |         |‖‖  ‖‖‖‖
+| ‖|| |         |‖5==5‖4‖
+| ‖|2-4=========5‖|  |‖‖‖
+| ‖|            ‖3|  |‖‖‖
+| ‖2------------3||  1‖2‖
+| ‖              1|   ‖ ‖
+5=5---------------6===7=6
+‖  2=======4      ‖   | ‖
+4------4--1‖      ‖   | ‖
+|      ‖   ‖      ‖   | ‖
+2-1 1--5===5------4---2 2
+"#;
+
+const HARD_25X25_2: &str = r#"
+1  2          1 3    4 2
+
+ 2   1          5       3
+                 2
+ 4 6    2         2 4   5
+
+    4  2         4 3 3 2
+      1
+                 2
+
+      3 3        1
+    5      5    7  5
+
+    1 2    4  1 1    1 1
+4  8               6    3
+                     2 3
+               2 1
+                    1  4
+
+   3         2
+
+   1
+5            5 5 4 4   4
+
+3                   1 1 2
+"#;
+const HARD_25X25_2_SOLN: &str = r#"
+1  2----------1 3====4=2 
+|  |            |        
+|2 | 1----------5-------3
+|‖ |            ‖2      ‖
+|4=6----2       ‖‖2=4===5
+|  ‖    |       ‖‖      |
+|  ‖4==2|       ‖4-3-3-2|
+|  ‖‖ 1 |       ‖| | | ||
+|  ‖‖ | |       ‖2 | | ||
+|  ‖‖ | |       ‖| | | ||
+|  ‖‖ 3=3       ‖1 | | ||
+|  ‖5======5====7==5 | ||
+|  ‖|      |    |  ‖ | ||
+|  ‖1 2====4--1 1  ‖ 1 1|
+4==8===============6----3
+|  ‖               | 2=3|
+|  ‖           2 1 |   ||
+|  ‖           ‖ | |1--4|
+|  ‖           ‖ | |   ‖|
+|  3         2 ‖ | |   ‖|
+|  |         ‖ ‖ | |   ‖|
+|  1         ‖ ‖ | |   ‖|
+5============5-5=4-4===4|
+‖                       |
+3-------------------1 1-2
+"#;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{RenderOptions, SolveState};
+
+    #[test]
+    fn test_puzzles_solve() {
+        for p in PUZZLES {
+            let b = p.board();
+            let (soln, _) = SolveState::new(&b).solve(3, 10_000).unwrap();
+            let compact = RenderOptions {
+                compact: true,
+                ..Default::default()
+            };
+            let rendered = b.serialize_styled_to_string(soln, compact);
+            assert_eq!(rendered, p.solution, "{}", p.name);
+        }
+        assert_eq!(by_difficulty(Difficulty::Hard).count(), 2);
+    }
+}
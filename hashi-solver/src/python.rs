@@ -0,0 +1,208 @@
+//! Python bindings, enabled with the `python` feature.
+//!
+//! ```python
+//! import hashi_solver
+//!
+//! board = hashi_solver.Board.parse(puzzle)
+//! soln, log = hashi_solver.solve(board)
+//! print(board.serialize(soln))
+//!
+//! board, soln = hashi_solver.generate(seed=7, width=10, height=10, islands=20)
+//! print(hashi_solver.rate(board)["hardness"])
+//! ```
+
+use pyo3::exceptions::PyValueError;
+use pyo3::prelude::*;
+use pyo3::types::PyDict;
+
+use crate::analyze::{difficulty_report, estimate_hardness};
+use crate::generator::GenerateOptions;
+use crate::{Board, Move, Solution, SolveOptions, SolveState};
+
+// A `Move` as a plain tuple of `(from, to, count)`.
+type PyMove = ((usize, usize), (usize, usize), u8);
+
+#[pyclass(name = "Board", frozen)]
+pub struct PyBoard(Board);
+
+#[pymethods]
+impl PyBoard {
+    #[staticmethod]
+    fn parse(s: &str) -> PyResult<Self> {
+        Board::parse(s)
+            .map(PyBoard)
+            .map_err(|e| PyValueError::new_err(e.to_string()))
+    }
+
+    fn serialize(&self, moves: Vec<PyMove>) -> PyResult<String> {
+        Ok(self.0.serialize_to_string(self.edges(moves)?))
+    }
+
+    fn render_svg(&self, moves: Vec<PyMove>) -> PyResult<String> {
+        Ok(self.0.render_svg_to_string(self.edges(moves)?))
+    }
+
+    fn __str__(&self) -> String {
+        self.0.serialize_to_string(vec![])
+    }
+}
+
+impl PyBoard {
+    fn edges(&self, moves: Vec<PyMove>) -> PyResult<Vec<usize>> {
+        let moves = moves
+            .into_iter()
+            .map(|(from, to, count)| Move { from, to, count })
+            .collect::<Vec<_>>();
+        Solution::from_moves(&self.0, &moves)
+            .map(|soln| soln.edges)
+            .map_err(PyValueError::new_err)
+    }
+}
+
+fn py_moves(moves: Vec<Move>) -> Vec<PyMove> {
+    moves
+        .into_iter()
+        .map(|mv| (mv.from, mv.to, mv.count))
+        .collect()
+}
+
+/// Solve the board, returning the bridges in the order they were placed, one at a time as
+/// `((x, y), (x, y), 1)`, and the reason for each placement.
+#[pyfunction]
+#[pyo3(signature = (board, max_depth = 3, max_visited = 10_000))]
+fn solve(
+    py: Python<'_>,
+    board: &PyBoard,
+    max_depth: usize,
+    max_visited: usize,
+) -> PyResult<(Vec<PyMove>, Vec<&'static str>)> {
+    let (moves, log) = py
+        .detach(|| SolveState::new(&board.0).solve_moves(max_depth, max_visited))
+        .map_err(PyValueError::new_err)?;
+    Ok((py_moves(moves), log))
+}
+
+/// Generate a board with a single solution from the seed, returning it along with the solution
+/// as `((x, y), (x, y), count)` for each pair of islands.
+/// Boards rated outside `min_hardness` to `max_hardness` (see `rate`) are passed over.
+#[pyfunction]
+#[pyo3(signature = (
+    seed,
+    width = 7,
+    height = 7,
+    islands = 12,
+    min_hardness = 0.0,
+    max_hardness = f64::INFINITY,
+))]
+fn generate(
+    py: Python<'_>,
+    seed: u64,
+    width: usize,
+    height: usize,
+    islands: usize,
+    min_hardness: f64,
+    max_hardness: f64,
+) -> PyResult<(PyBoard, Vec<PyMove>)> {
+    if width == 0 || height == 0 {
+        return Err(PyValueError::new_err("board is too small"));
+    }
+    let options = GenerateOptions {
+        size: (width, height),
+        islands,
+        min_hardness,
+        max_hardness,
+        ..Default::default()
+    };
+    let (board, soln) = py
+        .detach(|| crate::generator::generate(seed, &options))
+        .ok_or_else(|| PyValueError::new_err("no board found with these settings"))?;
+    let moves = py_moves(soln.moves(&board));
+    Ok((PyBoard(board), moves))
+}
+
+/// How hard the board is: its `hardness` score, higher being harder, the `techniques` a solve
+/// uses as `(name, count)` pairs, and how many `guesses` it makes and the `deepest_guess`.
+#[pyfunction]
+#[pyo3(signature = (board, max_depth = 3, max_visited = 10_000))]
+fn rate<'py>(
+    py: Python<'py>,
+    board: &PyBoard,
+    max_depth: usize,
+    max_visited: usize,
+) -> PyResult<Bound<'py, PyDict>> {
+    let options = SolveOptions {
+        max_depth,
+        max_visited,
+        ..Default::default()
+    };
+    let (hardness, report) = py
+        .detach(|| {
+            let report = difficulty_report(&board.0, &options)?;
+            Ok::<_, &'static str>((estimate_hardness(&board.0).score(), report))
+        })
+        .map_err(PyValueError::new_err)?;
+
+    let rating = PyDict::new(py);
+    rating.set_item("hardness", hardness)?;
+    let techniques = report
+        .techniques
+        .iter()
+        .map(|t| (t.name, t.count))
+        .collect::<Vec<_>>();
+    rating.set_item("techniques", techniques)?;
+    rating.set_item("guesses", report.guesses)?;
+    rating.set_item("deepest_guess", report.deepest_guess)?;
+    Ok(rating)
+}
+
+#[pymodule]
+fn hashi_solver(m: &Bound<'_, PyModule>) -> PyResult<()> {
+    m.add_class::<PyBoard>()?;
+    m.add_function(wrap_pyfunction!(solve, m)?)?;
+    m.add_function(wrap_pyfunction!(generate, m)?)?;
+    m.add_function(wrap_pyfunction!(rate, m)?)?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_generate_and_rate() {
+        Python::initialize();
+        Python::attach(|py| {
+            let (board, soln) = generate(py, 7, 7, 7, 12, 0.0, f64::INFINITY).unwrap();
+            let (solved, _) = solve(py, &board, 3, 10_000).unwrap();
+            assert_eq!(
+                board.serialize(solved).unwrap(),
+                board.serialize(soln.clone()).unwrap()
+            );
+            assert!(board.render_svg(soln).is_ok());
+            let nowhere = vec![((0, 0), (0, 0), 1)];
+            assert!(board.serialize(nowhere).is_err());
+
+            let rating = rate(py, &board, 3, 10_000).unwrap();
+            let hardness: f64 = rating
+                .get_item("hardness")
+                .unwrap()
+                .unwrap()
+                .extract()
+                .unwrap();
+            assert!(hardness >= 0.0);
+            let techniques: Vec<(String, usize)> = rating
+                .get_item("techniques")
+                .unwrap()
+                .unwrap()
+                .extract()
+                .unwrap();
+            assert!(!techniques.is_empty());
+
+            assert!(generate(py, 7, 0, 7, 12, 0.0, f64::INFINITY).is_err());
+            assert!(generate(py, 7, 1, 1, 2, 0.0, f64::INFINITY).is_err());
+            assert!(generate(py, 7, 7, 7, 12, 100.0, f64::INFINITY).is_err());
+            let unsolvable = PyBoard(Board::parse("1 1\n\n1 1").unwrap());
+            assert!(rate(py, &unsolvable, 3, 10_000).is_err());
+        });
+    }
+}
@@ -0,0 +1,207 @@
+//! Checks that the solver still does what it did on a corpus of boards, for catching unintended
+//! changes when reworking it. The corpus is a directory in which each `.txt` file is a board, in
+//! any format accepted by `Board::parse_any`, and the file of the same name ending in `.expected`
+//! holds what solving it gave last time as `key: value` lines. `record` writes those files, and
+//! `run` compares against them.
+
+use std::collections::BTreeMap;
+use std::fmt::{self, Write};
+use std::io;
+use std::path::{Path, PathBuf};
+
+use crate::analyze::{difficulty_report, estimate_hardness};
+use crate::{Board, SolveOptions, SolveState};
+
+pub const EXTENSION: &str = "txt";
+pub const EXPECTED_EXTENSION: &str = "expected";
+
+// A value which isn't what was recorded for the board.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Drift {
+    // The board's file name, without the extension.
+    pub board: String,
+    pub key: String,
+    // `None` if nothing was recorded.
+    pub expected: Option<String>,
+    // `None` if it's no longer given.
+    pub actual: Option<String>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Report {
+    pub boards: usize,
+    pub drift: Vec<Drift>,
+}
+
+impl Report {
+    pub fn passed(&self) -> bool {
+        self.drift.is_empty()
+    }
+}
+
+impl fmt::Display for Report {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let value = |v: &Option<String>| v.clone().unwrap_or_else(|| "(nothing)".into());
+        for d in &self.drift {
+            writeln!(
+                f,
+                "{}: {}: expected {}, got {}",
+                d.board,
+                d.key,
+                value(&d.expected),
+                value(&d.actual)
+            )?;
+        }
+        writeln!(
+            f,
+            "{} boards, {} differences",
+            self.boards,
+            self.drift.len()
+        )
+    }
+}
+
+// Solve every board in the corpus and compare the results with those recorded for it.
+pub fn run(corpus_dir: impl AsRef<Path>) -> io::Result<Report> {
+    let mut report = Report::default();
+    for (name, file) in boards(corpus_dir.as_ref())? {
+        let actual = observe(&read_board(&file)?);
+        let expected = match std::fs::read_to_string(file.with_extension(EXPECTED_EXTENSION)) {
+            Ok(s) => parse_expected(&s),
+            Err(e) if e.kind() == io::ErrorKind::NotFound => BTreeMap::new(),
+            Err(e) => return Err(e),
+        };
+
+        let mut keys = actual.keys().chain(expected.keys()).collect::<Vec<_>>();
+        keys.sort();
+        keys.dedup();
+        for key in keys {
+            let (expected, actual) = (expected.get(key), actual.get(key));
+            if expected != actual {
+                report.drift.push(Drift {
+                    board: name.clone(),
+                    key: key.clone(),
+                    expected: expected.cloned(),
+                    actual: actual.cloned(),
+                });
+            }
+        }
+        report.boards += 1;
+    }
+    Ok(report)
+}
+
+// Write down what solving each board in the corpus gives now, for `run` to compare against.
+// Returns the number of boards.
+pub fn record(corpus_dir: impl AsRef<Path>) -> io::Result<usize> {
+    let boards = boards(corpus_dir.as_ref())?;
+    for (_, file) in &boards {
+        let mut s = String::new();
+        for (key, value) in observe(&read_board(file)?) {
+            writeln!(s, "{}: {}", key, value).unwrap();
+        }
+        std::fs::write(file.with_extension(EXPECTED_EXTENSION), s)?;
+    }
+    Ok(boards.len())
+}
+
+// The boards in the corpus, by name, in order.
+fn boards(dir: &Path) -> io::Result<Vec<(String, PathBuf)>> {
+    let mut files = std::fs::read_dir(dir)?
+        .map(|e| e.map(|e| e.path()))
+        .collect::<io::Result<Vec<_>>>()?;
+    files.retain(|f| f.extension().is_some_and(|e| e == EXTENSION));
+    files.sort();
+    Ok(files
+        .into_iter()
+        .map(|f| {
+            let name = f.file_stem().unwrap().to_string_lossy().into_owned();
+            (name, f)
+        })
+        .collect())
+}
+
+fn read_board(file: &Path) -> io::Result<Board> {
+    Board::parse_any(&std::fs::read_to_string(file)?).map_err(|e| {
+        let msg = format!("{}: {}", file.display(), e);
+        io::Error::new(io::ErrorKind::InvalidData, msg)
+    })
+}
+
+fn parse_expected(s: &str) -> BTreeMap<String, String> {
+    s.lines()
+        .filter_map(|line| line.split_once(": "))
+        .map(|(key, value)| (key.into(), value.into()))
+        .collect()
+}
+
+// What's compared: the solution and how the solver got there, and the board's ratings.
+fn observe(board: &Board) -> BTreeMap<String, String> {
+    let options = SolveOptions::default();
+    let mut values = BTreeMap::new();
+    let mut state = SolveState::new(board);
+    let solution = match state.solve_with(&options) {
+        Ok((soln, _)) => {
+            let mut moves = board.moves(soln);
+            moves.sort();
+            moves
+                .iter()
+                .map(|mv| {
+                    let ((x1, y1), (x2, y2)) = (mv.from, mv.to);
+                    format!("{},{}-{},{}:{}", x1, y1, x2, y2, mv.count)
+                })
+                .collect::<Vec<_>>()
+                .join(" ")
+        }
+        Err(e) => format!("none ({})", e),
+    };
+    let stats = state.stats();
+    values.insert("solution".into(), solution);
+    values.insert("states_visited".into(), stats.states_visited.to_string());
+    values.insert("deductions".into(), stats.deductions.to_string());
+    values.insert("speculations".into(), stats.speculations.to_string());
+    values.insert(
+        "hardness".into(),
+        format!("{:.3}", estimate_hardness(board).score()),
+    );
+    if let Ok(report) = difficulty_report(board, &options) {
+        values.insert("rating".into(), report.to_string());
+    }
+    values
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_regression() {
+        let root = std::env::temp_dir().join(format!("hashi-regression-{}", std::process::id()));
+        std::fs::create_dir_all(&root).unwrap();
+        std::fs::write(root.join("easy.txt"), "3 3 2\n\n1   1").unwrap();
+        std::fs::write(root.join("square.txt"), "3 3\n\n3 3").unwrap();
+        std::fs::write(root.join("notes.md"), "not a board").unwrap();
+
+        // Nothing has been recorded yet.
+        let report = run(&root).unwrap();
+        assert_eq!(report.boards, 2);
+        assert!(!report.passed());
+        assert!(report.drift.iter().all(|d| d.expected.is_none()));
+
+        assert_eq!(record(&root).unwrap(), 2);
+        let report = run(&root).unwrap();
+        assert!(report.passed(), "{}", report);
+        assert_eq!(report.to_string(), "2 boards, 0 differences\n");
+
+        let expected = root.join("easy.expected");
+        let recorded = std::fs::read_to_string(&expected).unwrap();
+        let edited = recorded.replace("deductions: ", "deductions: 1");
+        std::fs::write(&expected, edited).unwrap();
+        let report = run(&root).unwrap();
+        assert_eq!(report.drift.len(), 1);
+        assert_eq!(report.drift[0].board, "easy");
+        assert_eq!(report.drift[0].key, "deductions");
+
+        std::fs::remove_dir_all(&root).unwrap();
+    }
+}
@@ -0,0 +1,345 @@
+//! Recordings of a solve, with every bridge the solver placed and took back and when, which can
+//! be saved alongside a bug report and played back to check that the solver still does the same.
+//!
+//! The format is plain text: a `hashi-replay 1` line, the board's variant and size, the solve
+//! options, one line per event, a blank line, and then the board's clues.
+
+use std::fmt::{self, Write};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use crate::{
+    Board, BoardTopology, BoardVariant, Move, Node, SolveEvent, SolveOptions, SolveState,
+    VisitedPolicy,
+};
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ReplayAction {
+    // A single bridge, so `mv.count` is always 1.
+    Added { mv: Move, reason: String },
+    Removed { mv: Move },
+    Solved,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ReplayEvent {
+    // Since the start of the solve.
+    pub at: Duration,
+    pub action: ReplayAction,
+}
+
+// Where playing a replay back first went differently. Either event is `None` if that side ran
+// out of events first.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Divergence {
+    pub index: usize,
+    pub recorded: Option<ReplayAction>,
+    pub replayed: Option<ReplayAction>,
+}
+
+#[derive(Debug, Clone)]
+pub struct Replay {
+    pub board: Board,
+    pub options: SolveOptions,
+    pub events: Vec<ReplayEvent>,
+}
+
+impl Replay {
+    // Solve the board, recording what the solver does along the way.
+    #[allow(clippy::type_complexity)]
+    pub fn record(
+        board: &Board,
+        options: &SolveOptions,
+    ) -> (Self, Result<(Vec<usize>, Vec<&'static str>), &'static str>) {
+        let start = Instant::now();
+        let events = Mutex::new(vec![]);
+        let observer = |event| {
+            let action = match event {
+                SolveEvent::EdgeAdded { edge, reason } => ReplayAction::Added {
+                    mv: board.move_for_edge(edge, 1),
+                    reason: reason.into(),
+                },
+                SolveEvent::EdgeRemoved { edge } => ReplayAction::Removed {
+                    mv: board.move_for_edge(edge, 1),
+                },
+                SolveEvent::Solved => ReplayAction::Solved,
+            };
+            // Kept to the precision they're written out with.
+            events.lock().unwrap().push(ReplayEvent {
+                at: Duration::from_micros(start.elapsed().as_micros() as u64),
+                action,
+            });
+        };
+        let result = SolveState::new(board)
+            .with_observer(&observer)
+            .solve_with(options);
+
+        let replay = Replay {
+            board: board.clone(),
+            options: *options,
+            events: events.into_inner().unwrap(),
+        };
+        (replay, result)
+    }
+
+    // Solve the board again with the same options, and check that the solver does the same
+    // things in the same order, returning where it first didn't. Timings aren't compared.
+    pub fn play(&self) -> Option<Divergence> {
+        let (replayed, _) = Replay::record(&self.board, &self.options);
+        let recorded = self.events.iter().map(|e| &e.action);
+        let mut replayed = replayed.events.into_iter().map(|e| e.action);
+        for (index, expected) in recorded.enumerate() {
+            let actual = replayed.next();
+            if actual.as_ref() != Some(expected) {
+                return Some(Divergence {
+                    index,
+                    recorded: Some(expected.clone()),
+                    replayed: actual,
+                });
+            }
+        }
+        replayed.next().map(|extra| Divergence {
+            index: self.events.len(),
+            recorded: None,
+            replayed: Some(extra),
+        })
+    }
+
+    pub fn load(s: &str) -> Result<Self, &'static str> {
+        let mut lines = s.lines();
+        if lines.next() != Some("hashi-replay 1") {
+            return Err("not a replay");
+        }
+        let (variant, size) = parse_variant(lines.next().ok_or("missing variant")?)?;
+        let options = parse_options(lines.next().ok_or("missing options")?)?;
+
+        let mut events = vec![];
+        for line in lines.by_ref() {
+            if line.is_empty() {
+                break;
+            }
+            events.push(parse_event(line)?);
+        }
+
+        let puzzle = lines.collect::<Vec<_>>().join("\n");
+        let parsed = Board::parse_variant(&puzzle, variant).map_err(|e| e.message)?;
+        let nodes = parsed.nodes.to_vec();
+        if nodes.iter().any(|n| n.pos.0 >= size.0 || n.pos.1 >= size.1) {
+            return Err("island outside the board");
+        }
+        let board = Board::new_sized(nodes, size, variant);
+        for event in &events {
+            if let ReplayAction::Added { mv, .. } | ReplayAction::Removed { mv } = event.action {
+                board
+                    .edge_for_move(mv)
+                    .ok_or("no bridge can connect these islands")?;
+            }
+        }
+        Ok(Replay {
+            board,
+            options,
+            events,
+        })
+    }
+}
+
+impl fmt::Display for Replay {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let variant = self.board.variant;
+        let (w, h) = self.board.size;
+        writeln!(f, "hashi-replay 1")?;
+        write!(f, "variant {} {}x{}", variant.max_bridges, w, h)?;
+        if variant.diagonal {
+            write!(f, " diagonal")?;
+        }
+        if variant.topology == BoardTopology::Torus {
+            write!(f, " torus")?;
+        }
+        writeln!(f)?;
+
+        let o = &self.options;
+        let policy = match o.visited_policy {
+            VisitedPolicy::Global => "global",
+            VisitedPolicy::DepthAware => "depth-aware",
+            VisitedPolicy::Off => "off",
+        };
+        write!(
+            f,
+            "options {} {} {} {} ",
+            o.max_depth, o.max_visited, policy, o.restarts
+        )?;
+        match o.max_memory_bytes {
+            Some(bytes) => writeln!(f, "{}", bytes)?,
+            None => writeln!(f, "-")?,
+        }
+
+        for event in &self.events {
+            write!(f, "{}", event.at.as_micros())?;
+            match &event.action {
+                ReplayAction::Added { mv, reason } => writeln!(
+                    f,
+                    " + {},{} {},{} {}",
+                    mv.from.0, mv.from.1, mv.to.0, mv.to.1, reason
+                )?,
+                ReplayAction::Removed { mv } => {
+                    writeln!(f, " - {},{} {},{}", mv.from.0, mv.from.1, mv.to.0, mv.to.1)?
+                }
+                ReplayAction::Solved => writeln!(f, " solved")?,
+            }
+        }
+        writeln!(f)?;
+
+        // The clues where they are on the board, so that the moves' positions line up.
+        let clues = self
+            .board
+            .nodes
+            .iter()
+            .map(|n| (n.pos, n))
+            .collect::<std::collections::BTreeMap<_, &Node>>();
+        for y in 0..h {
+            let mut line = String::new();
+            for x in 0..w {
+                match clues.get(&(x, y)) {
+                    Some(node) => write!(line, "{}", node)?,
+                    None => line.push(' '),
+                }
+            }
+            writeln!(f, "{}", line.trim_end())?;
+        }
+        Ok(())
+    }
+}
+
+fn parse_variant(line: &str) -> Result<(BoardVariant, (usize, usize)), &'static str> {
+    let mut words = line.split(' ');
+    if words.next() != Some("variant") {
+        return Err("missing variant");
+    }
+    let max_bridges = words
+        .next()
+        .and_then(|w| w.parse().ok())
+        .filter(|m| (1..=3).contains(m))
+        .ok_or("invalid bridge count")?;
+    let size = words.next().ok_or("missing size")?;
+    let size = parse_pair(size, 'x').ok_or("invalid size")?;
+    let mut variant = BoardVariant {
+        max_bridges,
+        ..Default::default()
+    };
+    for word in words {
+        match word {
+            "diagonal" => variant.diagonal = true,
+            "torus" => variant.topology = BoardTopology::Torus,
+            _ => return Err("unknown variant"),
+        }
+    }
+    Ok((variant, size))
+}
+
+fn parse_options(line: &str) -> Result<SolveOptions, &'static str> {
+    let words = line.split(' ').collect::<Vec<_>>();
+    let ["options", depth, visited, policy, restarts, memory] = words[..] else {
+        return Err("invalid options");
+    };
+    Ok(SolveOptions {
+        max_depth: depth.parse().map_err(|_| "invalid options")?,
+        max_visited: visited.parse().map_err(|_| "invalid options")?,
+        visited_policy: match policy {
+            "global" => VisitedPolicy::Global,
+            "depth-aware" => VisitedPolicy::DepthAware,
+            "off" => VisitedPolicy::Off,
+            _ => return Err("invalid options"),
+        },
+        restarts: restarts.parse().map_err(|_| "invalid options")?,
+        max_memory_bytes: match memory {
+            "-" => None,
+            bytes => Some(bytes.parse().map_err(|_| "invalid options")?),
+        },
+        ..Default::default()
+    })
+}
+
+fn parse_event(line: &str) -> Result<ReplayEvent, &'static str> {
+    let mut words = line.splitn(5, ' ');
+    let at = words
+        .next()
+        .and_then(|w| w.parse().ok())
+        .map(Duration::from_micros)
+        .ok_or("invalid event time")?;
+    let kind = words.next().ok_or("missing event")?;
+    if kind == "solved" {
+        return Ok(ReplayEvent {
+            at,
+            action: ReplayAction::Solved,
+        });
+    }
+    let mut pos = || {
+        words
+            .next()
+            .and_then(|w| parse_pair(w, ','))
+            .ok_or("invalid position")
+    };
+    let mv = Move {
+        from: pos()?,
+        to: pos()?,
+        count: 1,
+    };
+    let action = match kind {
+        "+" => ReplayAction::Added {
+            mv,
+            reason: words.next().ok_or("missing reason")?.into(),
+        },
+        "-" => ReplayAction::Removed { mv },
+        _ => return Err("unknown event"),
+    };
+    Ok(ReplayEvent { at, action })
+}
+
+fn parse_pair(s: &str, sep: char) -> Option<(usize, usize)> {
+    let (a, b) = s.split_once(sep)?;
+    Some((a.parse().ok()?, b.parse().ok()?))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_replay() {
+        let b = Board::parse("\n2 2 1\n\n  2 3\n\n1 1 2").unwrap();
+        let options = SolveOptions::default();
+        let (replay, result) = Replay::record(&b, &options);
+        assert!(result.is_ok());
+        assert!(replay
+            .events
+            .iter()
+            .any(|e| matches!(e.action, ReplayAction::Removed { .. })));
+        assert_eq!(replay.events.last().unwrap().action, ReplayAction::Solved);
+        assert_eq!(replay.play(), None);
+
+        let loaded = Replay::load(&replay.to_string()).unwrap();
+        assert_eq!(loaded.board.canonical_id(), b.canonical_id());
+        assert_eq!(loaded.options, options);
+        assert_eq!(loaded.events, replay.events);
+        assert_eq!(loaded.to_string(), replay.to_string());
+        assert_eq!(loaded.play(), None);
+
+        let mut edited = loaded.clone();
+        let removed = edited.events.remove(1);
+        assert_eq!(
+            edited.play(),
+            Some(Divergence {
+                index: 1,
+                recorded: Some(edited.events[1].action.clone()),
+                replayed: Some(removed.action),
+            })
+        );
+        assert_eq!(Replay::load("hashi-replay 2").err(), Some("not a replay"));
+        for count in ["0", "9", "x"] {
+            let text = replay
+                .to_string()
+                .replacen("variant 2 ", &format!("variant {} ", count), 1);
+            assert_eq!(Replay::load(&text).err(), Some("invalid bridge count"));
+        }
+    }
+}
@@ -0,0 +1,117 @@
+//! A record of the speculation done during a search, for visualizing why a board is hard.
+
+use alloc::string::String;
+use alloc::vec::Vec;
+use core::fmt::Write;
+
+use crate::Move;
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum BranchOutcome {
+    Solved,
+    Refuted(&'static str),
+    // The search was still inside the branch when it stopped, e.g. on hitting a limit.
+    Pending,
+}
+
+// A speculative edge, along with everything the search tried on top of it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Branch {
+    pub edge: usize,
+    pub mv: Move,
+    // How many speculative edges are in place including this one, starting at 1.
+    pub depth: usize,
+    pub outcome: BranchOutcome,
+    pub parent: Option<usize>,
+    pub children: Vec<usize>,
+}
+
+// Every speculative branch of a search, in the order they were tried. Branches refer to each
+// other by index.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct SearchTree {
+    pub branches: Vec<Branch>,
+}
+
+impl SearchTree {
+    // The branches tried directly from the starting state.
+    pub fn roots(&self) -> impl Iterator<Item = usize> + '_ {
+        (0..self.branches.len()).filter(|idx| self.branches[*idx].parent.is_none())
+    }
+
+    pub(crate) fn enter(&mut self, parent: Option<usize>, edge: usize, mv: Move) -> usize {
+        let idx = self.branches.len();
+        let depth = parent.map_or(1, |p| self.branches[p].depth + 1);
+        self.branches.push(Branch {
+            edge,
+            mv,
+            depth,
+            outcome: BranchOutcome::Pending,
+            parent,
+            children: Vec::new(),
+        });
+        if let Some(p) = parent {
+            self.branches[p].children.push(idx);
+        }
+        idx
+    }
+
+    // Render as a Graphviz digraph, with the starting state as the node `start`.
+    pub fn to_dot(&self) -> String {
+        let mut s = String::new();
+        writeln!(s, "digraph search {{").unwrap();
+        writeln!(s, "  start [shape=box];").unwrap();
+        for (idx, b) in self.branches.iter().enumerate() {
+            let (color, outcome) = match b.outcome {
+                BranchOutcome::Solved => ("green", "solved"),
+                BranchOutcome::Refuted(reason) => ("red", reason),
+                BranchOutcome::Pending => ("gray", "pending"),
+            };
+            writeln!(
+                s,
+                "  b{} [label=\"({}, {}) - ({}, {})\\n{}\", color={}];",
+                idx, b.mv.from.0, b.mv.from.1, b.mv.to.0, b.mv.to.1, outcome, color
+            )
+            .unwrap();
+            match b.parent {
+                Some(p) => writeln!(s, "  b{} -> b{};", p, idx).unwrap(),
+                None => writeln!(s, "  start -> b{};", idx).unwrap(),
+            }
+        }
+        writeln!(s, "}}").unwrap();
+        s
+    }
+
+    // Render as a JSON list of branches, each of the form
+    // `{"id": 0, "parent": null, "edge": 3, "from": [x, y], "to": [x, y], "depth": 1,
+    // "outcome": "refuted", "reason": "..."}`.
+    pub fn to_json(&self) -> String {
+        let mut s = String::from("[");
+        for (idx, b) in self.branches.iter().enumerate() {
+            if idx > 0 {
+                s.push(',');
+            }
+            let parent = match b.parent {
+                Some(p) => alloc::format!("{}", p),
+                None => String::from("null"),
+            };
+            let (outcome, reason) = match b.outcome {
+                BranchOutcome::Solved => ("solved", None),
+                BranchOutcome::Refuted(reason) => ("refuted", Some(reason)),
+                BranchOutcome::Pending => ("pending", None),
+            };
+            write!(
+                s,
+                r#"{{"id":{},"parent":{},"edge":{},"from":[{},{}],"to":[{},{}],"depth":{},"outcome":"{}""#,
+                idx, parent, b.edge, b.mv.from.0, b.mv.from.1, b.mv.to.0, b.mv.to.1, b.depth, outcome
+            )
+            .unwrap();
+            if let Some(reason) = reason {
+                write!(s, r#","reason":"{}""#, reason).unwrap();
+            }
+            s.push('}');
+        }
+        s.push(']');
+        s
+    }
+}
@@ -0,0 +1,396 @@
+//! Request and response types for serving the solver over HTTP, and a handler for `POST /solve`,
+//! `POST /generate` and `POST /hint` which doesn't depend on any web framework. Enabled with the
+//! `server` feature.
+//!
+//! With axum, for example:
+//!
+//! ```ignore
+//! async fn api(uri: Uri, body: Bytes) -> impl IntoResponse {
+//!     let r = hashi_solver::server::handle("POST", uri.path(), &body, &SolveOptions::default());
+//!     (StatusCode::from_u16(r.status).unwrap(), [(CONTENT_TYPE, r.content_type)], r.body)
+//! }
+//! let app = Router::new().route("/*path", post(api));
+//! ```
+
+use serde::{Deserialize, Serialize};
+
+use crate::generator::{generate_with, GenerateOptions};
+use crate::{solve_board, Board, HashiError, Move, ParseError, SolveOptions, SolveState};
+
+// Generated boards are capped at this many cells, and this many candidate boards are tried for
+// each, so a request can't tie up the server.
+pub const MAX_GENERATED_CELLS: usize = 100 * 100;
+pub const MAX_GENERATED_CANDIDATES: usize = 100;
+// Boards sent to `/solve` and `/hint` are capped at this many cells, as rendering the solution
+// takes time and memory in proportion to the whole board, not just its islands.
+pub const MAX_BOARD_CELLS: usize = 1000 * 1000;
+
+// A pair of islands and the number of bridges between them.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Bridge {
+    pub from: (usize, usize),
+    pub to: (usize, usize),
+    #[serde(default = "one")]
+    pub count: u8,
+}
+
+fn one() -> u8 {
+    1
+}
+
+impl From<Move> for Bridge {
+    fn from(mv: Move) -> Self {
+        Bridge {
+            from: mv.from,
+            to: mv.to,
+            count: mv.count,
+        }
+    }
+}
+
+impl From<Bridge> for Move {
+    fn from(b: Bridge) -> Self {
+        Move {
+            from: b.from,
+            to: b.to,
+            count: b.count,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SolveRequest {
+    // The board, in any format accepted by `Board::parse_any`.
+    pub puzzle: String,
+    // Lowers the server's search depth for this request. It can't be raised.
+    #[serde(default)]
+    pub max_depth: Option<usize>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SolveStep {
+    pub bridge: Bridge,
+    pub reason: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SolveResponse {
+    pub steps: Vec<SolveStep>,
+    // The solved board, as rendered by `Board::serialize_to_string`.
+    pub rendered: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct GenerateRequest {
+    pub width: usize,
+    pub height: usize,
+    // How many islands the board has, at least 2 and no more than there are cells.
+    pub islands: usize,
+    #[serde(default)]
+    pub seed: u64,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct GenerateResponse {
+    pub puzzle: String,
+    // The board's only solution, in the coordinates of `puzzle`.
+    pub solution: Vec<Bridge>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct HintRequest {
+    pub puzzle: String,
+    // The bridges placed so far.
+    #[serde(default)]
+    pub bridges: Vec<Bridge>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct HintResponse {
+    // A single bridge to place next.
+    pub bridge: Bridge,
+    pub reason: String,
+    // Whether the bridge follows from a single island's clue, rather than a search.
+    pub forced: bool,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ErrorResponse {
+    pub error: String,
+    // Where on the board the problem is, if it's down to a particular cell.
+    pub position: Option<(usize, usize)>,
+}
+
+impl ErrorResponse {
+    fn new(error: &str) -> Self {
+        ErrorResponse {
+            error: error.into(),
+            position: None,
+        }
+    }
+}
+
+impl From<ParseError> for ErrorResponse {
+    fn from(e: ParseError) -> Self {
+        ErrorResponse {
+            error: e.message.into(),
+            position: Some(e.pos),
+        }
+    }
+}
+
+impl From<HashiError> for ErrorResponse {
+    fn from(e: HashiError) -> Self {
+        match e {
+            HashiError::Parse(e) => e.into(),
+            HashiError::Solver(message) => ErrorResponse::new(message),
+        }
+    }
+}
+
+// Parse a board in any format `Board::parse_any` accepts, as long as it's no larger than
+// `MAX_BOARD_CELLS`.
+fn parse_board(puzzle: &str) -> Result<Board, ErrorResponse> {
+    let b = Board::parse_any(puzzle)?;
+    let (width, height) = b.size();
+    if width.saturating_mul(height) > MAX_BOARD_CELLS {
+        return Err(ErrorResponse::new("board is too large"));
+    }
+    Ok(b)
+}
+
+pub fn solve(req: &SolveRequest, options: &SolveOptions) -> Result<SolveResponse, ErrorResponse> {
+    let options = SolveOptions {
+        max_depth: req.max_depth.unwrap_or(usize::MAX).min(options.max_depth),
+        ..*options
+    };
+    let report = solve_board(parse_board(&req.puzzle)?, &options).map_err(ErrorResponse::new)?;
+    Ok(SolveResponse {
+        steps: report
+            .steps
+            .into_iter()
+            .map(|step| SolveStep {
+                bridge: step.mv.into(),
+                reason: step.reason.into(),
+            })
+            .collect(),
+        rendered: report.rendered,
+    })
+}
+
+// A board with a single solution, generated from the seed with `generator::generate_with`.
+// `options` limits how hard each candidate is searched for other solutions.
+pub fn generate(
+    req: &GenerateRequest,
+    options: &SolveOptions,
+) -> Result<GenerateResponse, ErrorResponse> {
+    // Bridges need an empty cell between the islands they join.
+    if req.width.max(req.height) < 3 || req.islands < 2 {
+        return Err(ErrorResponse::new("board is too small"));
+    }
+    let cells = req.width.saturating_mul(req.height);
+    if cells > MAX_GENERATED_CELLS {
+        return Err(ErrorResponse::new("board is too large"));
+    }
+    if req.islands > cells {
+        return Err(ErrorResponse::new("more islands than cells on the board"));
+    }
+    let generate_options = GenerateOptions {
+        size: (req.width, req.height),
+        islands: req.islands,
+        max_candidates: MAX_GENERATED_CANDIDATES,
+        max_visited: options.max_visited,
+        ..Default::default()
+    };
+    let (b, solution) = generate_with(req.seed, &generate_options, |b, _| {
+        b.nodes.len() == req.islands
+    })
+    .ok_or_else(|| ErrorResponse::new("could not generate a board with that many islands"))?;
+
+    // The puzzle text leaves out the empty rows and columns around the islands.
+    let min_x = b.nodes.iter().map(|n| n.pos.0).min().unwrap_or(0);
+    let min_y = b.nodes.iter().map(|n| n.pos.1).min().unwrap_or(0);
+    let shift = |(x, y): (usize, usize)| (x - min_x, y - min_y);
+    Ok(GenerateResponse {
+        puzzle: b.to_puzzle_string(),
+        solution: solution
+            .moves(&b)
+            .into_iter()
+            .map(|mv| Bridge {
+                from: shift(mv.from),
+                to: shift(mv.to),
+                count: mv.count,
+            })
+            .collect(),
+    })
+}
+
+pub fn hint(req: &HintRequest, options: &SolveOptions) -> Result<HintResponse, ErrorResponse> {
+    let b = parse_board(&req.puzzle)?;
+    let mut state = SolveState::new(&b);
+    for bridge in &req.bridges {
+        for _ in 0..bridge.count {
+            let mv = Move {
+                count: 1,
+                ..(*bridge).into()
+            };
+            state.add_move(mv, "user").map_err(|e| ErrorResponse {
+                error: e.into(),
+                position: Some(bridge.from),
+            })?;
+        }
+    }
+    let hint = state
+        .next_hint(options.max_depth, options.max_visited)
+        .map_err(ErrorResponse::new)?;
+    Ok(HintResponse {
+        bridge: hint.mv.into(),
+        reason: hint.reason.into(),
+        forced: hint.forced,
+    })
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Response {
+    pub status: u16,
+    pub content_type: &'static str,
+    pub body: String,
+}
+
+impl Response {
+    fn json<T: Serialize>(status: u16, body: &T) -> Self {
+        Response {
+            status,
+            content_type: "application/json",
+            body: serde_json::to_string(body).expect("responses serialize to JSON"),
+        }
+    }
+}
+
+// Route a request to `solve`, `generate` or `hint` by its path, with JSON bodies both ways.
+// `options` are the server's limits on how hard to search.
+pub fn handle(method: &str, path: &str, body: &[u8], options: &SolveOptions) -> Response {
+    fn call<Req: for<'de> Deserialize<'de>, Resp: Serialize>(
+        body: &[u8],
+        f: impl FnOnce(&Req) -> Result<Resp, ErrorResponse>,
+    ) -> Response {
+        let req = match serde_json::from_slice(body) {
+            Ok(req) => req,
+            Err(e) => return Response::json(400, &ErrorResponse::new(&e.to_string())),
+        };
+        match f(&req) {
+            Ok(resp) => Response::json(200, &resp),
+            Err(e) => Response::json(422, &e),
+        }
+    }
+
+    let route = match path {
+        "/solve" | "/generate" | "/hint" => path,
+        _ => return Response::json(404, &ErrorResponse::new("not found")),
+    };
+    if method != "POST" {
+        return Response::json(405, &ErrorResponse::new("method not allowed"));
+    }
+    match route {
+        "/solve" => call(body, |req| solve(req, options)),
+        "/generate" => call(body, |req| generate(req, options)),
+        _ => call(body, |req| hint(req, options)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_handle() {
+        let options = SolveOptions::default();
+        let r = handle(
+            "POST",
+            "/solve",
+            br#"{"puzzle": "3 3 2\n\n1   1"}"#,
+            &options,
+        );
+        assert_eq!(r.status, 200);
+        let solved: SolveResponse = serde_json::from_str(&r.body).unwrap();
+        let b = Board::parse("3 3 2\n\n1   1").unwrap();
+        let moves = solved
+            .steps
+            .iter()
+            .map(|s| s.bridge.into())
+            .collect::<Vec<_>>();
+        let soln = crate::Solution::from_moves(&b, &moves).unwrap();
+        assert_eq!(b.serialize_to_string(soln.edges), solved.rendered);
+
+        let r = handle("POST", "/solve", br#"{"puzzle": "1 1\n\n1 1"}"#, &options);
+        assert_eq!(r.status, 422);
+        let r = handle("POST", "/solve", br#"{"puzzle": "x"}"#, &options);
+        assert_eq!(
+            serde_json::from_str::<ErrorResponse>(&r.body).unwrap(),
+            ErrorResponse {
+                error: "unexpected character (only expected 1-8 or ?)".into(),
+                position: Some((0, 0)),
+            }
+        );
+        assert_eq!(handle("POST", "/solve", b"{", &options).status, 400);
+        assert_eq!(handle("GET", "/solve", b"", &options).status, 405);
+        assert_eq!(handle("POST", "/", b"", &options).status, 404);
+
+        let body = br#"{"width": 7, "height": 7, "islands": 8, "seed": 3}"#;
+        let r = handle("POST", "/generate", body, &options);
+        assert_eq!(r.status, 200);
+        let generated: GenerateResponse = serde_json::from_str(&r.body).unwrap();
+        let b = Board::parse(&generated.puzzle).unwrap();
+        assert_eq!(b.nodes.len(), 8);
+        assert!(SolveState::new(&b).solve_with(&options).is_ok());
+
+        for body in [
+            &br#"{"width": 2, "height": 2, "islands": 4}"#[..],
+            br#"{"width": 3, "height": 3, "islands": 50}"#,
+            br#"{"width": 3, "height": 3, "islands": 9}"#,
+        ] {
+            assert_eq!(handle("POST", "/generate", body, &options).status, 422);
+        }
+
+        let req = HintRequest {
+            puzzle: generated.puzzle,
+            bridges: generated.solution[1..].to_vec(),
+        };
+        let r = handle(
+            "POST",
+            "/hint",
+            serde_json::to_string(&req).unwrap().as_bytes(),
+            &options,
+        );
+        assert_eq!(r.status, 200);
+        let hint: HintResponse = serde_json::from_str(&r.body).unwrap();
+        let missing = generated.solution[0];
+        assert!([(missing.from, missing.to), (missing.to, missing.from)]
+            .contains(&(hint.bridge.from, hint.bridge.to)));
+
+        // Any format `/solve` takes works for `/hint` too.
+        let req = HintRequest {
+            puzzle: crate::formats::to_sgt(&b).unwrap(),
+            bridges: vec![],
+        };
+        let r = handle(
+            "POST",
+            "/hint",
+            serde_json::to_string(&req).unwrap().as_bytes(),
+            &options,
+        );
+        assert_eq!(r.status, 200);
+
+        // Only a couple of islands, but a huge board to render them on.
+        let huge = r#"{"width": 4294967295, "height": 4294967295, "islands": [[0,0,1],[2,0,1]]}"#;
+        for path in ["/solve", "/hint"] {
+            let body = serde_json::json!({ "puzzle": huge }).to_string();
+            let r = handle("POST", path, body.as_bytes(), &options);
+            assert_eq!(r.status, 422);
+            assert_eq!(
+                serde_json::from_str::<ErrorResponse>(&r.body).unwrap(),
+                ErrorResponse::new("board is too large")
+            );
+        }
+    }
+}
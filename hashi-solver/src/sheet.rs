@@ -0,0 +1,153 @@
+//! Printable pages of puzzles, e.g. for a booklet of generated boards: an HTML document laying
+//! the boards out in a grid, each with its title and difficulty from its metadata, and optionally
+//! the solutions on a page of their own. Printing it from a browser gives a PDF.
+
+use alloc::string::String;
+use core::fmt::Write;
+
+use crate::Board;
+
+pub struct SheetPuzzle<'a> {
+    pub board: &'a Board,
+    // The bridges of its solution, for the solutions page.
+    pub solution: Option<&'a [usize]>,
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct SheetOptions {
+    pub columns: usize,
+    // Add a page with the solutions of the puzzles which have one, after the puzzles.
+    pub solutions: bool,
+}
+
+impl Default for SheetOptions {
+    fn default() -> Self {
+        SheetOptions {
+            columns: 2,
+            solutions: true,
+        }
+    }
+}
+
+const STYLE: &str = "body { font-family: sans-serif; }
+.grid { display: grid; gap: 1cm; }
+figure { margin: 0; break-inside: avoid; }
+figure svg { width: 100%; height: auto; }
+figcaption { font-weight: bold; margin-bottom: 0.3em; }
+.difficulty { font-weight: normal; color: #555; }
+.solutions { break-before: page; }";
+
+pub fn render_html(puzzles: &[SheetPuzzle], options: &SheetOptions) -> String {
+    let mut s = String::new();
+    s.push_str("<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n");
+    writeln!(s, "<style>\n{}\n</style>\n</head>\n<body>", STYLE).unwrap();
+    section(
+        &mut s,
+        "Puzzles",
+        "puzzles",
+        puzzles,
+        options,
+        |_| Some(&[]),
+    );
+    if options.solutions && puzzles.iter().any(|p| p.solution.is_some()) {
+        section(&mut s, "Solutions", "solutions", puzzles, options, |p| {
+            p.solution
+        });
+    }
+    s.push_str("</body>\n</html>\n");
+    s
+}
+
+fn section<'a>(
+    s: &mut String,
+    heading: &str,
+    class: &str,
+    puzzles: &[SheetPuzzle<'a>],
+    options: &SheetOptions,
+    bridges: impl Fn(&SheetPuzzle<'a>) -> Option<&'a [usize]>,
+) {
+    writeln!(s, "<section class=\"{}\">\n<h1>{}</h1>", class, heading).unwrap();
+    writeln!(
+        s,
+        "<div class=\"grid\" style=\"grid-template-columns: repeat({}, 1fr)\">",
+        options.columns.max(1)
+    )
+    .unwrap();
+    for (i, puzzle) in puzzles.iter().enumerate() {
+        let Some(bridges) = bridges(puzzle) else {
+            continue;
+        };
+        let metadata = puzzle.board.metadata();
+        s.push_str("<figure>\n<figcaption>");
+        match &metadata.title {
+            Some(title) => escape(s, title),
+            None => write!(s, "Puzzle {}", i + 1).unwrap(),
+        }
+        if let Some(difficulty) = &metadata.difficulty {
+            s.push_str(" <span class=\"difficulty\">");
+            escape(s, difficulty);
+            s.push_str("</span>");
+        }
+        s.push_str("</figcaption>\n");
+        s.push_str(&puzzle.board.render_svg_to_string(bridges.iter().copied()));
+        s.push_str("</figure>\n");
+    }
+    s.push_str("</div>\n</section>\n");
+}
+
+fn escape(s: &mut String, text: &str) {
+    for c in text.chars() {
+        match c {
+            '<' => s.push_str("&lt;"),
+            '>' => s.push_str("&gt;"),
+            '&' => s.push_str("&amp;"),
+            '"' => s.push_str("&quot;"),
+            c => s.push(c),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::BoardMetadata;
+
+    #[test]
+    fn test_render_html() {
+        let titled = Board::parse("3 3\n\n3 3")
+            .unwrap()
+            .with_metadata(BoardMetadata {
+                title: Some("Square <1>".into()),
+                difficulty: Some("easy".into()),
+                ..Default::default()
+            });
+        let plain = Board::parse("2 4 2\n\n  2").unwrap();
+        let soln = [0, 0, 1];
+        let puzzles = [
+            SheetPuzzle {
+                board: &titled,
+                solution: Some(&soln),
+            },
+            SheetPuzzle {
+                board: &plain,
+                solution: None,
+            },
+        ];
+
+        let html = render_html(&puzzles, &SheetOptions::default());
+        assert!(html.contains("Square &lt;1&gt; <span class=\"difficulty\">easy</span>"));
+        assert!(html.contains("<figcaption>Puzzle 2</figcaption>"));
+        assert!(html.contains("repeat(2, 1fr)"));
+        // Both puzzles, then the one solution.
+        assert_eq!(html.matches("<svg").count(), 3);
+        assert_eq!(html.matches("<line").count(), 3);
+
+        let options = SheetOptions {
+            columns: 3,
+            solutions: false,
+        };
+        let html = render_html(&puzzles, &options);
+        assert!(!html.contains("Solutions"));
+        assert_eq!(html.matches("<svg").count(), 2);
+    }
+}
@@ -0,0 +1,278 @@
+//! An exhaustive solver, for getting exact solution counts on small boards where the results of
+//! `SolveState` might be in doubt.
+
+use alloc::collections::BTreeSet;
+use alloc::vec;
+use alloc::vec::Vec;
+
+use crate::{Board, Solution};
+
+// Every solution of the board, up to `limit` of them, found by trying every number of bridges on
+// every edge. This is exponential in the number of edges, so it's only practical for boards of a
+// dozen or so islands. The edges of each solution are in ascending order.
+pub fn brute_force(board: &Board, limit: usize) -> Vec<Solution> {
+    let ends = board
+        .edges
+        .iter()
+        .map(|e| {
+            let (p1, p2) = e.endpoints();
+            let idx = |pos| board.nodes.iter().position(|n| n.pos == pos).unwrap();
+            (idx(p1), idx(p2))
+        })
+        .collect::<Vec<_>>();
+    // Once the search is past the last edge of an island, the island can't get any more bridges.
+    let mut last_edge = vec![None; board.nodes.len()];
+    for (edge, (n1, n2)) in ends.iter().enumerate() {
+        last_edge[*n1] = Some(edge);
+        last_edge[*n2] = Some(edge);
+    }
+
+    let mut search = Reference {
+        board,
+        ends,
+        last_edge,
+        counts: vec![0; board.edges.len()],
+        bridges: vec![0; board.nodes.len()],
+        found: vec![],
+        limit,
+    };
+    search.search(0);
+    search.found
+}
+
+// Whether the bridges, given as a count for each edge, can all be on the board at once: no more
+// between a pair of islands than the variant allows or at an island than its clue allows, and
+// none crossing. Worked out afresh from the islands' positions, as a check on the solver's own
+// bookkeeping.
+pub fn check_bridges(board: &Board, counts: &[u8]) -> Result<(), &'static str> {
+    if counts.len() != board.edges.len() {
+        return Err("wrong number of edges");
+    }
+    let mut bridges = vec![0usize; board.nodes.len()];
+    for (edge, count) in counts.iter().enumerate() {
+        if *count == 0 {
+            continue;
+        }
+        if *count > board.variant.max_bridges {
+            return Err("too many bridges between a pair of islands");
+        }
+        let (p1, p2) = board.edges[edge].endpoints();
+        for pos in [p1, p2] {
+            let n = board
+                .nodes
+                .iter()
+                .position(|n| n.pos == pos)
+                .ok_or("bridge doesn't end at an island")?;
+            bridges[n] += *count as usize;
+        }
+        let crossed = board
+            .edge_intersections
+            .get(edge)
+            .any(|e| counts[e.index()] > 0);
+        if crossed {
+            return Err("bridges cross");
+        }
+    }
+    let overfull = board
+        .nodes
+        .iter()
+        .zip(&bridges)
+        .any(|(node, n)| node.max_bridges().is_some_and(|m| *n > m as usize));
+    if overfull {
+        return Err("island has more bridges than its clue");
+    }
+    Ok(())
+}
+
+// Whether the bridges, given as a count for each edge, solve the board: they're all allowed
+// together, every island has as many as its clue calls for, and they join up every island.
+pub fn check_solution(board: &Board, counts: &[u8]) -> Result<(), &'static str> {
+    check_bridges(board, counts)?;
+    let index = |pos| board.nodes.iter().position(|n| n.pos == pos).unwrap();
+    let ends = board
+        .edges
+        .iter()
+        .map(|e| {
+            let (p1, p2) = e.endpoints();
+            (index(p1), index(p2))
+        })
+        .collect::<Vec<_>>();
+    let mut bridges = vec![0usize; board.nodes.len()];
+    for ((n1, n2), count) in ends.iter().zip(counts) {
+        bridges[*n1] += *count as usize;
+        bridges[*n2] += *count as usize;
+    }
+    let incomplete = board
+        .nodes
+        .iter()
+        .zip(&bridges)
+        .any(|(node, n)| *n < node.min_bridges() as usize);
+    if incomplete {
+        return Err("island has fewer bridges than its clue");
+    }
+    if board.nodes.is_empty() {
+        return Ok(());
+    }
+
+    let mut connected = BTreeSet::new();
+    let mut stk = vec![0];
+    while let Some(n) = stk.pop() {
+        if !connected.insert(n) {
+            continue;
+        }
+        for ((n1, n2), count) in ends.iter().zip(counts) {
+            if *count > 0 && (*n1 == n || *n2 == n) {
+                stk.push(if *n1 == n { *n2 } else { *n1 });
+            }
+        }
+    }
+    if connected.len() != board.nodes.len() {
+        return Err("islands are not all connected");
+    }
+    Ok(())
+}
+
+struct Reference<'b> {
+    board: &'b Board,
+    ends: Vec<(usize, usize)>,
+    last_edge: Vec<Option<usize>>,
+    counts: Vec<u8>,
+    bridges: Vec<u8>,
+    found: Vec<Solution>,
+    limit: usize,
+}
+
+impl Reference<'_> {
+    fn search(&mut self, edge: usize) {
+        if self.found.len() >= self.limit {
+            return;
+        }
+        if edge == self.counts.len() {
+            if self.is_solution() {
+                let edges = self
+                    .counts
+                    .iter()
+                    .enumerate()
+                    .flat_map(|(idx, count)| core::iter::repeat_n(idx, *count as usize))
+                    .collect();
+                self.found.push(Solution { edges });
+            }
+            return;
+        }
+
+        let (n1, n2) = self.ends[edge];
+        let crosses = self
+            .board
+            .edge_intersections
+            .get(edge)
+            .any(|e| e.index() < edge && self.counts[e.index()] > 0);
+        let most = if crosses {
+            0
+        } else {
+            self.board.variant.max_bridges
+        };
+
+        for count in 0..=most {
+            let fits = |n: usize| {
+                self.board.nodes[n]
+                    .max_bridges()
+                    .is_none_or(|m| self.bridges[n] + count <= m)
+            };
+            if !fits(n1) || !fits(n2) {
+                break;
+            }
+            self.counts[edge] = count;
+            self.bridges[n1] += count;
+            self.bridges[n2] += count;
+            let finished = |n: usize| {
+                self.last_edge[n] != Some(edge)
+                    || self.bridges[n] >= self.board.nodes[n].min_bridges()
+            };
+            if finished(n1) && finished(n2) {
+                self.search(edge + 1);
+            }
+            self.bridges[n1] -= count;
+            self.bridges[n2] -= count;
+        }
+        self.counts[edge] = 0;
+    }
+
+    fn is_solution(&self) -> bool {
+        let complete = self.board.nodes.iter().enumerate().all(|(n, node)| {
+            self.bridges[n] >= node.min_bridges()
+                && node.max_bridges().is_none_or(|m| self.bridges[n] <= m)
+        });
+        if !complete {
+            return false;
+        }
+        if self.board.nodes.is_empty() {
+            return true;
+        }
+
+        let mut connected = BTreeSet::new();
+        let mut stk = vec![0];
+        while let Some(n) = stk.pop() {
+            if !connected.insert(n) {
+                continue;
+            }
+            for (edge, (n1, n2)) in self.ends.iter().enumerate() {
+                if self.counts[edge] > 0 && (*n1 == n || *n2 == n) {
+                    stk.push(if *n1 == n { *n2 } else { *n1 });
+                }
+            }
+        }
+        connected.len() == self.board.nodes.len()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_brute_force() {
+        let b = Board::parse("3 3\n\n3 3").unwrap();
+        assert_eq!(brute_force(&b, 8).len(), 2);
+        assert_eq!(brute_force(&b, 1).len(), 1);
+
+        let b = Board::parse("2 1 1").unwrap();
+        assert_eq!(brute_force(&b, 8), vec![]);
+    }
+
+    #[test]
+    fn test_check_solution() {
+        let b = Board::parse("3 3\n\n3 3").unwrap();
+        for soln in brute_force(&b, 8) {
+            let mut counts = vec![0; b.edges.len()];
+            for edge in soln.edges {
+                counts[edge] += 1;
+            }
+            assert_eq!(check_solution(&b, &counts), Ok(()));
+            counts[0] -= 1;
+            assert_eq!(check_bridges(&b, &counts), Ok(()));
+            assert_eq!(
+                check_solution(&b, &counts),
+                Err("island has fewer bridges than its clue")
+            );
+        }
+        assert_eq!(
+            check_bridges(&b, &[3, 0, 0, 0]),
+            Err("too many bridges between a pair of islands")
+        );
+
+        // Two pairs of islands joined up separately.
+        let b = Board::parse("1 1\n\n1 1").unwrap();
+        let across = b.edge_between((0, 0), (2, 0)).unwrap();
+        let below = b.edge_between((0, 2), (2, 2)).unwrap();
+        let mut counts = vec![0; b.edges.len()];
+        counts[across] = 1;
+        counts[below] = 1;
+        assert_eq!(
+            check_solution(&b, &counts),
+            Err("islands are not all connected")
+        );
+
+        let b = Board::parse(" 1\n1 1\n 1").unwrap();
+        assert_eq!(check_bridges(&b, &[1, 1]), Err("bridges cross"));
+    }
+}
@@ -0,0 +1,553 @@
+//! The deductions people use when solving by hand, on their own, for tutorials and for
+//! generators which want a cheap look at a board without running the solver.
+
+use alloc::collections::BTreeMap;
+use alloc::vec;
+use alloc::vec::Vec;
+
+use crate::{Board, EdgeId, Move, NodeId, SolveState};
+
+// Deductions beyond what a single island's clue gives, which the solver can be asked to try
+// before it speculates. None are used by default.
+#[derive(Debug, Copy, Clone, Default, PartialEq, Eq)]
+pub struct Techniques {
+    // A group of islands already joined by bridges needs as many more bridges as its clues call
+    // for. Those between its own islands count twice, so if they can't take enough, the rest
+    // have to leave the group, which can force a bridge out of it.
+    pub region_capacity: bool,
+    // Every island has to be reached, so a pair of islands which is the only way between two
+    // parts of the board needs a bridge.
+    pub bottleneck: bool,
+    // Assuming the puzzle has a single solution, a loop of four islands can't end up with bridges
+    // which could be moved round the loop, one off each of two opposite sides and onto the other
+    // two, without cutting any island off. Only used when asked for by name, since it's wrong for
+    // puzzles with several solutions.
+    pub uniqueness: bool,
+}
+
+pub(crate) const REGION_CAPACITY: &str = "the group of islands cannot be completed without it";
+pub(crate) const BOTTLENECK: &str = "the islands would be cut in two without it";
+pub(crate) const UNIQUENESS: &str = "otherwise the bridges around the loop could be swapped";
+
+// Everything known about each technique, which the solver, `check_derivation` and the
+// descriptions given to players all go by.
+struct Technique {
+    name: &'static str,
+    reason: &'static str,
+    preconditions: &'static str,
+    assumes_unique: bool,
+    flag: fn(&mut Techniques) -> &mut bool,
+    // The edges it would place another bridge on, each with the island it's forced from.
+    find: fn(&SolveState) -> Vec<(NodeId, EdgeId)>,
+    // A board on which, once the bridges in `placed` are, it forces `forced`.
+    example: &'static str,
+    placed: &'static [Move],
+    forced: Move,
+}
+
+const TECHNIQUES: [Technique; 3] = [
+    Technique {
+        name: "Region capacity",
+        reason: REGION_CAPACITY,
+        preconditions: "A group of islands joined by bridges needs more bridges than the edges \
+            between its own islands can take, or is not yet joined to the rest, and only one of \
+            the edges leaving it can make up the difference.",
+        assumes_unique: false,
+        flag: |t| &mut t.region_capacity,
+        find: region_capacity,
+        example: "2 3 1",
+        placed: &[Move {
+            from: (0, 0),
+            to: (2, 0),
+            count: 1,
+        }],
+        forced: Move {
+            from: (2, 0),
+            to: (4, 0),
+            count: 1,
+        },
+    },
+    Technique {
+        name: "Bottleneck",
+        reason: BOTTLENECK,
+        preconditions: "A pair of islands without a bridge yet is the only way left between two \
+            parts of the board.",
+        assumes_unique: false,
+        flag: |t| &mut t.bottleneck,
+        find: bottleneck,
+        example: "    2 2\n\n2 3 3 2\n\n2 2",
+        placed: &[],
+        forced: Move {
+            from: (2, 2),
+            to: (4, 2),
+            count: 1,
+        },
+    },
+    Technique {
+        name: "Uniqueness",
+        reason: UNIQUENESS,
+        preconditions: "Four islands form a loop in which two opposite sides already have \
+            bridges, one of them a double, and one of the other two sides can't take a double. \
+            Only for puzzles with a single solution.",
+        assumes_unique: true,
+        flag: |t| &mut t.uniqueness,
+        find: uniqueness,
+        example: "2 3\n\n3 4",
+        placed: &[
+            Move {
+                from: (2, 0),
+                to: (2, 2),
+                count: 2,
+            },
+            Move {
+                from: (0, 0),
+                to: (0, 2),
+                count: 1,
+            },
+        ],
+        forced: Move {
+            from: (0, 2),
+            to: (2, 2),
+            count: 1,
+        },
+    },
+];
+
+impl Techniques {
+    // Every technique which holds whether or not the puzzle has a single solution.
+    pub fn all() -> Self {
+        let mut techniques = Techniques::default();
+        for t in TECHNIQUES.iter().filter(|t| !t.assumes_unique) {
+            *(t.flag)(&mut techniques) = true;
+        }
+        techniques
+    }
+
+    // The techniques which placed any of the bridges in a solve's log.
+    pub(crate) fn used_in(log: &[&'static str]) -> Self {
+        let mut techniques = Techniques::default();
+        for t in &TECHNIQUES {
+            *(t.flag)(&mut techniques) = log.contains(&t.reason);
+        }
+        techniques
+    }
+
+    fn enabled(mut self, t: &Technique) -> bool {
+        *(t.flag)(&mut self)
+    }
+}
+
+// Whether the reason was given for a bridge placed by one of the techniques.
+pub(crate) fn is_reason(reason: &str) -> bool {
+    TECHNIQUES.iter().any(|t| t.reason == reason)
+}
+
+// A bridge forced by one of the enabled techniques, with the island it was forced from.
+pub(crate) fn find_forced_edge(state: &SolveState) -> Option<(NodeId, EdgeId, &'static str)> {
+    TECHNIQUES
+        .iter()
+        .filter(|t| state.techniques.enabled(t))
+        .find_map(|t| {
+            let (node, edge) = *(t.find)(state).first()?;
+            Some((node, edge, t.reason))
+        })
+}
+
+// Whether the technique behind `reason` would place another bridge on the edge.
+pub(crate) fn forces(state: &SolveState, edge: EdgeId, reason: &str) -> bool {
+    TECHNIQUES
+        .iter()
+        .filter(|t| t.reason == reason)
+        .any(|t| (t.find)(state).iter().any(|(_, e)| *e == edge))
+}
+
+// The name of the technique behind a reason in the solver's log, counting its deductions from a
+// single island's clue as techniques too. `None` for guesses and bridges placed by the user.
+pub fn name(reason: &str) -> Option<&'static str> {
+    match reason {
+        "only viable edge" => Some("Only neighbour"),
+        "must include all of the remaining edges" => Some("Every neighbour"),
+        "other edges cannot complete the island without this one" => Some("Island capacity"),
+        _ => TECHNIQUES
+            .iter()
+            .find(|t| t.reason == reason)
+            .map(|t| t.name),
+    }
+}
+
+// A technique as explained to players, e.g. for a strategy guide.
+#[derive(Debug, Clone)]
+pub struct Description {
+    pub name: &'static str,
+    // The reason given in the solver's log for the bridges it places.
+    pub reason: &'static str,
+    // When it places a bridge.
+    pub preconditions: &'static str,
+    // Whether it only holds for puzzles with a single solution.
+    pub assumes_unique: bool,
+    // A small board, with the bridges in `placed` already on it, on which the technique places
+    // the bridge in `forced`.
+    pub example: Board,
+    pub placed: Vec<Move>,
+    pub forced: Move,
+}
+
+// Every technique the solver can be asked to use, in the order it tries them.
+pub fn describe_all() -> Vec<Description> {
+    TECHNIQUES
+        .iter()
+        .map(|t| Description {
+            name: t.name,
+            reason: t.reason,
+            preconditions: t.preconditions,
+            assumes_unique: t.assumes_unique,
+            example: Board::parse(t.example).expect("technique examples parse"),
+            placed: t.placed.to_vec(),
+            forced: t.forced,
+        })
+        .collect()
+}
+
+// The edges leaving each group of two or more bridged islands which must take another bridge,
+// each with the island it leaves from. Single islands are left to the solver's own deductions.
+fn region_capacity(state: &SolveState) -> Vec<(NodeId, EdgeId)> {
+    let board = state.board;
+    let mut region = vec![usize::MAX; board.nodes.len()];
+    let mut forced = vec![];
+    for start in 0..board.nodes.len() {
+        if region[start] != usize::MAX {
+            continue;
+        }
+        let mut members = vec![start];
+        region[start] = start;
+        let mut idx = 0;
+        while let Some(&node) = members.get(idx) {
+            idx += 1;
+            for edge in board.node_edges.get(node) {
+                if state.edge_counts[edge.index()].count() == 0 {
+                    continue;
+                }
+                let (n1, n2) = board.edge_nodes(edge);
+                let other = if n1.index() == node { n2 } else { n1 };
+                if region[other.index()] == usize::MAX {
+                    region[other.index()] = start;
+                    members.push(other.index());
+                }
+            }
+        }
+        if members.len() < 2 {
+            continue;
+        }
+
+        let needed = members
+            .iter()
+            .map(|n| state.required(NodeId::new(*n)) as usize)
+            .sum::<usize>();
+        let (mut inside, mut leaving) = (0, vec![]);
+        for node in &members {
+            for (edge, slots) in state.available_edges_for_node(NodeId::new(*node)) {
+                let (n1, n2) = board.edge_nodes(edge);
+                if region[n1.index()] == region[n2.index()] {
+                    // Seen from both ends, and each bridge counts for both.
+                    inside += slots as usize;
+                } else {
+                    leaving.push((NodeId::new(*node), edge, slots as usize));
+                }
+            }
+        }
+        // Unless the group is the whole board, at least one bridge has to leave it to join it
+        // up with the rest.
+        let whole = members.len() == board.nodes.len();
+        let needed_out = needed.saturating_sub(inside).max(if whole { 0 } else { 1 });
+        let out = leaving.iter().map(|(_, _, slots)| slots).sum::<usize>();
+        // Nothing can be deduced for a group which can't be completed.
+        if needed_out > out {
+            continue;
+        }
+        for (node, edge, slots) in leaving {
+            if needed_out > out - slots {
+                forced.push((node, edge));
+            }
+        }
+    }
+    forced
+}
+
+// The edges without a bridge yet which every way of joining up the islands has to use, each with
+// the first of its islands. Crossing edges are all counted as usable, so this holds whichever of
+// them end up with the bridges.
+fn bottleneck(state: &SolveState) -> Vec<(NodeId, EdgeId)> {
+    let board = state.board;
+    let usable = (0..board.edges.len())
+        .map(|e| {
+            let edge = EdgeId::new(e);
+            state.edge_counts[e].count() > 0 || state.edge_capacity(edge) > 0
+        })
+        .collect::<Vec<_>>();
+    // Whether every island can be reached without using the given edge.
+    let connected_without = |skip: Option<usize>| {
+        let mut seen = vec![false; board.nodes.len()];
+        let mut stack = vec![0];
+        seen[0] = true;
+        let mut reached = 1;
+        while let Some(node) = stack.pop() {
+            for edge in board.node_edges.get(node) {
+                if !usable[edge.index()] || Some(edge.index()) == skip {
+                    continue;
+                }
+                let (n1, n2) = board.edge_nodes(edge);
+                let other = if n1.index() == node { n2 } else { n1 };
+                if !seen[other.index()] {
+                    seen[other.index()] = true;
+                    reached += 1;
+                    stack.push(other.index());
+                }
+            }
+        }
+        reached == board.nodes.len()
+    };
+
+    // Nothing can be deduced if the islands can't be joined up at all.
+    if board.nodes.is_empty() || !connected_without(None) {
+        return vec![];
+    }
+    (0..board.edges.len())
+        .filter(|e| usable[*e] && state.edge_counts[*e].count() == 0)
+        .filter(|e| !connected_without(Some(*e)))
+        .map(|e| (board.edge_nodes[e].0, EdgeId::new(e)))
+        .collect()
+}
+
+// The edges which need more bridges so that no loop of four islands a-b-c-d ends up with bridges
+// which could be swapped round it. Taking a bridge off each of b-c and d-a and adding one to each
+// of a-b and c-d leaves every island with as many bridges as before, and the four stay joined up
+// unless both of the sides losing a bridge only had one. So if b-c and d-a already have bridges,
+// one of them a double, and a-b can't be filled, c-d has to be. Loops with a side crossing
+// another edge are left alone, since the bridges moved could be in the way.
+fn uniqueness(state: &SolveState) -> Vec<(NodeId, EdgeId)> {
+    let board = state.board;
+    let max_bridges = board.variant.max_bridges;
+    let other = |edge: EdgeId, node: usize| {
+        let (n1, n2) = board.edge_nodes(edge);
+        if n1.index() == node {
+            n2.index()
+        } else {
+            n1.index()
+        }
+    };
+    let count = |edge: EdgeId| state.edge_counts[edge.index()].count();
+    let crossed = |edge: EdgeId| board.edge_intersections.get(edge.index()).next().is_some();
+
+    let mut forced = vec![];
+    for a in 0..board.nodes.len() {
+        for ab in board.node_edges.get(a) {
+            let b = other(ab, a);
+            for da in board.node_edges.get(a).filter(|e| *e != ab) {
+                let d = other(da, a);
+                for bc in board.node_edges.get(b).filter(|e| *e != ab) {
+                    let c = other(bc, b);
+                    if c == d {
+                        continue;
+                    }
+                    let Some(cd) = board.node_edges.get(c).find(|e| other(*e, c) == d) else {
+                        continue;
+                    };
+                    let sides = [ab, bc, cd, da];
+                    if sides.iter().any(|e| crossed(*e))
+                        || count(bc) == 0
+                        || count(da) == 0
+                        || count(bc).max(count(da)) < 2
+                        || count(ab) + state.edge_capacity(ab) >= max_bridges
+                    {
+                        continue;
+                    }
+                    // Nothing can be deduced if c-d can't be filled either.
+                    if count(cd) < max_bridges
+                        && count(cd) + state.edge_capacity(cd) == max_bridges
+                        && !forced.iter().any(|(_, e)| *e == cd)
+                    {
+                        forced.push((board.edge_nodes(cd).0, cd));
+                    }
+                }
+            }
+        }
+    }
+    forced
+}
+
+// The bridges which follow from each island's clue and the room its neighbours have, before
+// anything else is placed: the classic openings like an 8 in the middle of the board, a 6 on an
+// edge or a 4 in a corner needing double bridges all round, and a 7, 5 or 3 in the same places
+// needing at least a single bridge to each neighbour. A neighbour's clue limits how many bridges
+// it can take, e.g. an island with a 3 next to a 1 and one other island needs a double bridge to
+// the other. Bridges crossing each other aren't taken into account, so fewer moves are found than
+// the solver's first deductions. Moves are in edge order, one per pair of islands.
+pub fn initial_forced_moves(board: &Board) -> Vec<Move> {
+    let max_bridges = board.variant.max_bridges;
+    let capacity = |edge: usize| {
+        let (n1, n2) = board.edge_nodes[edge];
+        [n1, n2]
+            .iter()
+            .filter_map(|n| board.nodes[n.index()].max_bridges())
+            .fold(max_bridges, u8::min)
+    };
+
+    let mut forced = BTreeMap::new();
+    for (idx, node) in board.nodes.iter().enumerate() {
+        let edges = board.node_edges.get(idx).map(|e| e.index());
+        let total = edges.clone().map(|e| capacity(e) as usize).sum::<usize>();
+        for edge in edges {
+            // Whatever the other edges can't take has to go on this one.
+            let others = total - capacity(edge) as usize;
+            let needed = (node.min_bridges() as usize).saturating_sub(others) as u8;
+            if needed > 0 {
+                let count = forced.entry(edge).or_insert(0);
+                *count = needed.max(*count);
+            }
+        }
+    }
+    forced
+        .into_iter()
+        .map(|(edge, count)| board.move_for_edge(edge, count))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Solution, SolveOptions, SolveState};
+
+    #[test]
+    fn test_initial_forced_moves() {
+        let b = Board::parse("  2  \n\n2 8 2\n\n  2").unwrap();
+        let moves = initial_forced_moves(&b);
+        assert_eq!(moves.len(), 4);
+        assert!(moves.iter().all(|mv| mv.count == 2));
+
+        // The 3 can only have one bridge to the 1, so needs two to the 2.
+        let b = Board::parse("1 3 2").unwrap();
+        assert_eq!(
+            initial_forced_moves(&b),
+            vec![
+                Move {
+                    from: (0, 0),
+                    to: (2, 0),
+                    count: 1
+                },
+                Move {
+                    from: (2, 0),
+                    to: (4, 0),
+                    count: 2
+                },
+            ]
+        );
+
+        // Every forced move is part of the solution.
+        let b = Board::parse("\n 2    4\n3  4 3\n\n 1 2  3\n4    3\n\n3  3  3").unwrap();
+        let (soln, _) = SolveState::new(&b)
+            .solve_with(&SolveOptions::default())
+            .unwrap();
+        let solved = b.moves(soln);
+        let moves = initial_forced_moves(&b);
+        assert!(!moves.is_empty());
+        for mv in moves {
+            let placed = solved.iter().find(|s| (s.from, s.to) == (mv.from, mv.to));
+            assert!(placed.is_some_and(|s| s.count >= mv.count), "{:?}", mv);
+        }
+    }
+
+    #[test]
+    fn test_region_capacity() {
+        // With a bridge between the 2 and the 3, the pair can only be joined to the rest through the 1.
+        let b = Board::parse("2 3 1").unwrap();
+        let edge = |a: usize, z: usize| {
+            (0..b.edges.len())
+                .find(|e| b.edge_nodes[*e] == (NodeId::new(a), NodeId::new(z)))
+                .unwrap()
+        };
+        let mut state = SolveState::new(&b).with_techniques(Techniques::all());
+        assert!(region_capacity(&state).is_empty());
+        state.add_edge(edge(0, 1), "user");
+        assert_eq!(
+            region_capacity(&state),
+            vec![(NodeId::new(1), EdgeId::new(edge(1, 2)))]
+        );
+        assert!(forces(&state, EdgeId::new(edge(1, 2)), REGION_CAPACITY));
+        assert!(!forces(&state, EdgeId::new(edge(0, 1)), REGION_CAPACITY));
+
+        // Disabled, nothing is forced.
+        state.techniques = Techniques::default();
+        assert_eq!(find_forced_edge(&state), None);
+    }
+
+    #[test]
+    fn test_uniqueness() {
+        // With a double bridge on the right and a single one on the left, a single bridge across
+        // the top and bottom could be swapped round for a double one along the bottom.
+        let b = Board::parse("2 3\n\n3 4").unwrap();
+        let edge = |a: usize, z: usize| {
+            (0..b.edges.len())
+                .find(|e| b.edge_nodes[*e] == (NodeId::new(a), NodeId::new(z)))
+                .unwrap()
+        };
+        let mut state = SolveState::new(&b).with_techniques(Techniques {
+            uniqueness: true,
+            ..Default::default()
+        });
+        state.add_edge(edge(1, 3), "user");
+        state.add_edge(edge(1, 3), "user");
+        assert!(uniqueness(&state).is_empty());
+        state.add_edge(edge(0, 2), "user");
+        assert_eq!(
+            uniqueness(&state),
+            vec![(NodeId::new(2), EdgeId::new(edge(2, 3)))]
+        );
+        assert!(forces(&state, EdgeId::new(edge(2, 3)), UNIQUENESS));
+
+        // Not unless asked for.
+        assert!(!Techniques::all().uniqueness);
+        state.techniques = Techniques::default();
+        assert_eq!(find_forced_edge(&state), None);
+    }
+
+    #[test]
+    fn test_describe_all() {
+        let descriptions = describe_all();
+        assert_eq!(descriptions.len(), TECHNIQUES.len());
+        for d in descriptions {
+            let b = &d.example;
+            let only = TECHNIQUES.iter().find(|t| t.name == d.name).unwrap();
+            let mut techniques = Techniques::default();
+            *(only.flag)(&mut techniques) = true;
+            let mut state = SolveState::new(b).with_techniques(techniques);
+            for mv in &d.placed {
+                let edge = b.edge_between(mv.from, mv.to).unwrap();
+                for _ in 0..mv.count {
+                    state.add_edge(edge, "user");
+                }
+            }
+            let forced = b.edge_between(d.forced.from, d.forced.to).unwrap();
+            let (_, edge, reason) = find_forced_edge(&state).unwrap();
+            assert_eq!((edge.index(), reason), (forced, d.reason), "{}", d.name);
+            assert_eq!(d.forced.count, 1);
+        }
+    }
+
+    #[test]
+    fn test_bottleneck() {
+        // The 3s are the only way between the two squares, though either could take all of its
+        // bridges from its own square.
+        let b = Board::parse("    2 2\n\n2 3 3 2\n\n2 2").unwrap();
+        let mut state = SolveState::new(&b).with_techniques(Techniques::all());
+        let forced = bottleneck(&state);
+        assert_eq!(forced.len(), 1);
+        assert_eq!(
+            b.edge_nodes[forced[0].1.index()],
+            (NodeId::new(3), NodeId::new(4))
+        );
+        assert!(forces(&state, forced[0].1, BOTTLENECK));
+
+        let (soln, log) = state.solve_with(&SolveOptions::default()).unwrap();
+        assert_eq!(Solution { edges: soln }.check_derivation(&b, &log), Ok(()));
+    }
+}
@@ -0,0 +1,99 @@
+//! A brute-force reference solver and random board generators, for cross-checking `SolveState`
+//! in property tests. The generators of solvable boards live in the `generator` module, and are
+//! re-exported here. Enabled with the `testing` feature.
+
+use alloc::vec;
+use alloc::vec::Vec;
+
+pub use crate::generator::{random_puzzle, random_puzzle_shaped, Shape};
+use crate::rng::Rng;
+use crate::{Board, BoardVariant, ClueKind, Node, Solution};
+
+// Every solution of the board, up to `limit` of them, as found by `solver::brute_force`.
+pub fn reference_solutions(board: &Board, limit: usize) -> Vec<Solution> {
+    crate::solver::brute_force(board, limit)
+}
+
+// A board with islands scattered at random, each with a random clue. Most of these boards have no
+// solution, which makes them good for checking that the solver doesn't claim one.
+pub fn random_board(seed: u64, size: (usize, usize), variant: BoardVariant) -> Board {
+    let mut rng = Rng(seed);
+    let mut nodes = vec![];
+    for y in 0..size.1 {
+        for x in 0..size.0 {
+            if rng.range(0, 2) > 0 {
+                continue;
+            }
+            let (n, kind) = match rng.range(0, 9) {
+                0 => (None, ClueKind::Exact),
+                1 => (Some(rng.range(1, 4) as u8), ClueKind::AtLeast),
+                2 => (Some(rng.range(1, 4) as u8), ClueKind::AtMost),
+                _ => (
+                    Some(rng.range(1, 4 * variant.max_bridges as usize) as u8),
+                    ClueKind::Exact,
+                ),
+            };
+            nodes.push(Node {
+                n,
+                kind,
+                pos: (x, y),
+            });
+        }
+    }
+    Board::new_sized(nodes, size, variant)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{BoardTopology, SolveState};
+
+    fn solve(board: &Board) -> Option<Solution> {
+        let mut state = SolveState::new(board);
+        let (mut edges, _) = state.solve(board.edges.len(), 100_000).ok()?;
+        edges.sort();
+        Some(Solution { edges })
+    }
+
+    proptest::proptest! {
+        #[test]
+        fn test_solver_finds_generated_solutions(
+            seed in proptest::num::u64::ANY,
+            (w, h) in (3usize..7, 3usize..7),
+            islands in 2usize..10,
+            max_bridges in 1u8..=3,
+        ) {
+            let variant = BoardVariant { max_bridges, ..Default::default() };
+            let (b, mut solution) = random_puzzle(seed, (w, h), islands, variant);
+            solution.edges.sort();
+            let solutions = reference_solutions(&b, 1_000);
+            proptest::prop_assert!(solutions.contains(&solution));
+
+            let found = solve(&b);
+            proptest::prop_assert!(found.is_some());
+            proptest::prop_assert!(solutions.contains(&found.unwrap()));
+
+            let (edges, log) = SolveState::new(&b).solve(b.edges.len(), 100_000).unwrap();
+            proptest::prop_assert_eq!(Solution { edges }.check_derivation(&b, &log), Ok(()));
+        }
+
+        #[test]
+        fn test_solver_agrees_on_random_boards(
+            seed in proptest::num::u64::ANY,
+            (w, h) in (2usize..5, 2usize..5),
+            (max_bridges, diagonal, torus) in (1u8..=3, proptest::bool::ANY, proptest::bool::ANY),
+        ) {
+            let variant = BoardVariant {
+                max_bridges,
+                diagonal,
+                topology: if torus { BoardTopology::Torus } else { BoardTopology::Plane },
+            };
+            let b = random_board(seed, (w, h), variant);
+            let solutions = reference_solutions(&b, 1_000);
+            match solve(&b) {
+                Some(found) => proptest::prop_assert!(solutions.contains(&found)),
+                None => proptest::prop_assert_eq!(solutions, vec![]),
+            }
+        }
+    }
+}
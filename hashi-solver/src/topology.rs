@@ -0,0 +1,817 @@
+//! Board geometry. The solver itself only deals with islands, the edges between them and which
+//! edges cross; everything which depends on the shape of the grid lives behind `Topology`.
+
+use alloc::collections::{BTreeMap, BTreeSet};
+use alloc::string::ToString;
+use alloc::vec;
+use alloc::vec::Vec;
+use core::fmt;
+
+use crate::{BoardVariant, Node, NumEdges, RenderOptions, RenderStyle};
+
+// A new kind of grid is added as another `BoardTopology` with its own implementation of this,
+// so it stays internal to the crate. Islands are still placed at `(x, y)` positions which the
+// text rendering lays out in rows and columns, and `Board::symmetries` and `Board::scramble`
+// reflect and rotate those positions as a square grid; a grid with other symmetries would need
+// its own versions of those.
+pub(crate) trait Topology {
+    // The candidate edges between islands, i.e. the pairs of islands which can see each other.
+    fn edges(&self, nodes: &[Node], size: (usize, usize), variant: BoardVariant) -> Vec<Edge>;
+
+    // Whether bridges along both edges would cross each other.
+    fn intersects(&self, a: Edge, b: Edge) -> bool;
+
+    // The cells the edge runs through, including the islands at either end.
+    fn cells(&self, edge: Edge) -> Vec<(usize, usize)>;
+
+    // The step the edge takes from its first endpoint towards its second.
+    fn direction(&self, edge: Edge) -> (isize, isize);
+
+    // Whether the edge leaves the board and comes back in from the other side, so that its
+    // endpoints don't line up along its direction.
+    fn wraps(&self, edge: Edge) -> bool;
+
+    // The character drawn along the edge for its bridges in the text rendering.
+    fn glyph(&self, edge: Edge, num_edges: NumEdges) -> char;
+
+    #[allow(clippy::too_many_arguments)]
+    fn render_text(
+        &self,
+        nodes: &[Node],
+        edges: &[Edge],
+        edge_counts: &dyn Fn(usize) -> NumEdges,
+        candidates: &dyn Fn(usize) -> bool,
+        size: (usize, usize),
+        options: RenderOptions,
+        io: &mut dyn fmt::Write,
+    ) -> fmt::Result;
+
+    fn render_svg(
+        &self,
+        nodes: &[Node],
+        edges: &[Edge],
+        edge_counts: &dyn Fn(usize) -> NumEdges,
+        candidates: &dyn Fn(usize) -> bool,
+        io: &mut dyn fmt::Write,
+    ) -> fmt::Result;
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub(crate) enum Edge {
+    V {
+        x: usize,
+        y_range: (usize, usize),
+    },
+    H {
+        x_range: (usize, usize),
+        y: usize,
+    },
+    // Diagonal, running left to right (`from.0 < to.0`) either upwards or downwards.
+    D {
+        from: (usize, usize),
+        to: (usize, usize),
+    },
+    // Horizontal and vertical lines on a torus which leave the board past the right or bottom
+    // edge at the first coordinate and come back in from the other side to the second.
+    WH {
+        x_range: (usize, usize),
+        y: usize,
+        width: usize,
+    },
+    WV {
+        x: usize,
+        y_range: (usize, usize),
+        height: usize,
+    },
+}
+
+impl Edge {
+    fn interval_intersects(a: (usize, usize), b: (usize, usize)) -> bool {
+        Self::value_in_interval(a.0, b) || Self::value_in_interval(a.1, b)
+    }
+
+    fn value_in_interval(v: usize, interval: (usize, usize)) -> bool {
+        assert!(interval.0 < interval.1);
+        v > interval.0 && v < interval.1
+    }
+
+    // Whether the segments cross at a point in the interior of both, using doubled coordinates
+    // so that diagonals crossing between grid points are caught too.
+    fn segments_cross(
+        a: ((usize, usize), (usize, usize)),
+        b: ((usize, usize), (usize, usize)),
+    ) -> bool {
+        fn orientation(p: (usize, usize), q: (usize, usize), r: (usize, usize)) -> i64 {
+            let (px, py, qx, qy, rx, ry) = (
+                p.0 as i64, p.1 as i64, q.0 as i64, q.1 as i64, r.0 as i64, r.1 as i64,
+            );
+            ((qx - px) * (ry - py) - (qy - py) * (rx - px)).signum()
+        }
+
+        orientation(a.0, a.1, b.0) * orientation(a.0, a.1, b.1) < 0
+            && orientation(b.0, b.1, a.0) * orientation(b.0, b.1, a.1) < 0
+    }
+
+    fn intersects(self, other: Edge) -> bool {
+        match (self, other) {
+            (Edge::WH { .. } | Edge::WV { .. }, _) | (_, Edge::WH { .. } | Edge::WV { .. }) => {
+                // Wrapping lines are compared cell by cell, since they can't be treated as a
+                // single segment.
+                let interior = |e: Edge| e.points().skip(1).take(e.length() - 1);
+                interior(self).any(|p| interior(other).any(|q| p == q))
+            }
+            (Edge::D { .. }, _) | (_, Edge::D { .. }) => {
+                Self::segments_cross(self.endpoints(), other.endpoints())
+            }
+            (
+                Edge::V { x, y_range },
+                Edge::V {
+                    x: x2,
+                    y_range: y_range2,
+                },
+            ) => x == x2 && Self::interval_intersects(y_range, y_range2),
+            (
+                Edge::H { y, x_range },
+                Edge::H {
+                    y: y2,
+                    x_range: x_range2,
+                },
+            ) => y == y2 && Self::interval_intersects(x_range, x_range2),
+            (Edge::H { y, x_range }, Edge::V { x, y_range })
+            | (Edge::V { x, y_range }, Edge::H { y, x_range }) => {
+                Self::value_in_interval(x, x_range) && Self::value_in_interval(y, y_range)
+            }
+        }
+    }
+
+    pub(crate) fn endpoints(self) -> ((usize, usize), (usize, usize)) {
+        match self {
+            Edge::H { y, x_range } => ((x_range.0, y), (x_range.1, y)),
+            Edge::V { x, y_range } => ((x, y_range.0), (x, y_range.1)),
+            Edge::D { from, to } => (from, to),
+            Edge::WH { x_range, y, .. } => ((x_range.0, y), (x_range.1, y)),
+            Edge::WV { x, y_range, .. } => ((x, y_range.0), (x, y_range.1)),
+        }
+    }
+
+    // The step the edge takes from its first endpoint towards its second.
+    fn direction(self) -> (isize, isize) {
+        match self {
+            Edge::H { .. } | Edge::WH { .. } => (1, 0),
+            Edge::V { .. } | Edge::WV { .. } => (0, 1),
+            Edge::D { from, to } if to.1 > from.1 => (1, 1),
+            Edge::D { .. } => (1, -1),
+        }
+    }
+
+    // The cells the edge runs through, including the islands at either end.
+    fn points(self) -> impl Iterator<Item = (usize, usize)> + Clone {
+        (0..=self.length()).map(move |i| self.point(i))
+    }
+
+    // The number of steps from one end of the edge to the other.
+    pub(crate) fn length(self) -> usize {
+        match self {
+            Edge::H { x_range, .. } => x_range.1 - x_range.0,
+            Edge::V { y_range, .. } => y_range.1 - y_range.0,
+            Edge::D { from, to } => to.0 - from.0,
+            Edge::WH { x_range, width, .. } => width - x_range.0 + x_range.1,
+            Edge::WV {
+                y_range, height, ..
+            } => height - y_range.0 + y_range.1,
+        }
+    }
+
+    fn point(self, i: usize) -> (usize, usize) {
+        match self {
+            Edge::H { y, x_range } => (x_range.0 + i, y),
+            Edge::V { x, y_range } => (x, y_range.0 + i),
+            Edge::D { from, to } if to.1 > from.1 => (from.0 + i, from.1 + i),
+            Edge::D { from, .. } => (from.0 + i, from.1 - i),
+            Edge::WH { x_range, y, width } => ((x_range.0 + i) % width, y),
+            Edge::WV { x, y_range, height } => (x, (y_range.0 + i) % height),
+        }
+    }
+
+    fn as_char(self, num_edges: NumEdges) -> char {
+        match (self, num_edges.count()) {
+            (_, 0) => ' ',
+            (Edge::H { .. } | Edge::WH { .. }, 1) => '-',
+            (Edge::V { .. } | Edge::WV { .. }, 1) => '|',
+            (Edge::H { .. } | Edge::WH { .. }, 2) => '=',
+            (Edge::V { .. } | Edge::WV { .. }, 2) => '‖',
+            (Edge::H { .. } | Edge::WH { .. }, _) => '≡',
+            (Edge::V { .. } | Edge::WV { .. }, _) => '⦀',
+            (Edge::D { from, to }, n) => match (to.1 > from.1, n) {
+                (true, 1) => '\\',
+                (false, 1) => '/',
+                (true, 2) => '⋱',
+                (false, 2) => '⋰',
+                (true, _) => '⧹',
+                (false, _) => '⧸',
+            },
+        }
+    }
+}
+
+// The bridges running through a single cell of the text rendering.
+#[derive(Debug, Copy, Clone, Default)]
+struct Cell {
+    // The most bridges along a horizontal, vertical or diagonal edge through the cell.
+    h: u8,
+    v: u8,
+    d: u8,
+    // The character for the edges through the cell, or `None` if they'd be drawn differently.
+    only: Option<char>,
+    edges: usize,
+    // Whether an edge with no bridges yet, but which could still take some, runs through the cell.
+    candidate: bool,
+}
+
+impl Cell {
+    fn add(&mut self, edge: Edge, num_edges: NumEdges) {
+        let count = num_edges.count();
+        match edge {
+            Edge::H { .. } | Edge::WH { .. } => self.h = self.h.max(count),
+            Edge::V { .. } | Edge::WV { .. } => self.v = self.v.max(count),
+            Edge::D { .. } => self.d = self.d.max(count),
+        }
+        let c = edge.as_char(num_edges);
+        self.only = match self.only {
+            _ if self.edges == 0 => Some(c),
+            Some(only) if only == c => Some(c),
+            _ => None,
+        };
+        self.edges += 1;
+    }
+
+    // Overlapping bridges only happen in partial or mistaken solutions. The classic style marks
+    // any overlap with `+`. The box drawing style keeps the weight of horizontal and vertical
+    // bridges in the crossing character, and marks anything involving a diagonal with `╳`.
+    fn as_char(self, style: RenderStyle) -> char {
+        match (style, self.only) {
+            (RenderStyle::Ascii, None) if self.edges == 0 && self.candidate => '.',
+            (_, None) if self.edges == 0 && self.candidate => '·',
+            (_, None) if self.edges == 0 => ' ',
+            (RenderStyle::Classic, Some(c)) => c,
+            (RenderStyle::Classic | RenderStyle::Ascii, None) => '+',
+            (RenderStyle::Ascii, Some(c)) => ascii_glyph(c),
+            (RenderStyle::BoxDrawing, _) if self.d > 0 && self.edges > 1 => '╳',
+            (RenderStyle::BoxDrawing, Some(c)) => match c {
+                '-' => '─',
+                '=' => '═',
+                '|' => '│',
+                '‖' => '║',
+                c => c,
+            },
+            (RenderStyle::BoxDrawing, None) => match (self.h.min(2), self.v.min(2)) {
+                (1, 1) => '┼',
+                (1, 2) => '╫',
+                (2, 1) => '╪',
+                (2, 2) => '╬',
+                // Bridges overlapping along the same line, which can happen around a torus.
+                (_, 0) => '═',
+                _ => '║',
+            },
+        }
+    }
+}
+
+// How many characters wide each column of the text rendering is, for the columns holding a clue
+// longer than a single character. The rest are one character wide.
+pub(crate) fn column_widths(nodes: &[Node]) -> BTreeMap<usize, usize> {
+    let mut widths = BTreeMap::new();
+    for node in nodes {
+        let len = node.to_string().chars().count();
+        if len > 1 {
+            let width = widths.entry(node.pos.0).or_insert(1);
+            *width = len.max(*width);
+        }
+    }
+    widths
+}
+
+pub(crate) fn width(widths: &BTreeMap<usize, usize>, x: usize) -> usize {
+    widths.get(&x).copied().unwrap_or(1)
+}
+
+// The character for a horizontal bridge on its own, to draw it across a widened column.
+fn horizontal_char(num_edges: NumEdges, style: RenderStyle) -> char {
+    let mut cell = Cell::default();
+    cell.add(
+        Edge::H {
+            x_range: (0, 1),
+            y: 0,
+        },
+        num_edges,
+    );
+    cell.as_char(style)
+}
+
+// The stand-in for a classic glyph in the ASCII style. Which way the bridge runs is clear from
+// the islands at either end, so the same character can be used for more than one.
+pub(crate) fn ascii_glyph(c: char) -> char {
+    match c {
+        '‖' => '"',
+        '≡' | '⦀' => '#',
+        '⋱' | '⋰' => '%',
+        '⧹' | '⧸' => '&',
+        c => c,
+    }
+}
+
+// The markers past the right and bottom edges of a torus for rows and columns with a bridge
+// wrapping around them.
+pub(crate) const WRAP_MARKERS: (char, char) = ('>', 'v');
+
+const SVG_CELL_SIZE: usize = 40;
+const SVG_ISLAND_RADIUS: usize = 15;
+const SVG_BRIDGE_OFFSET: usize = 4;
+
+// The classic square grid, where bridges run horizontally or vertically (and optionally
+// diagonally), and may wrap around the edges of the board on a torus.
+pub(crate) struct SquareGrid {
+    pub(crate) wrap: bool,
+}
+
+impl Topology for SquareGrid {
+    fn edges(&self, nodes: &[Node], size: (usize, usize), variant: BoardVariant) -> Vec<Edge> {
+        let mut edges = vec![];
+
+        // compute horizontal lines
+        let mut nodes = nodes.to_vec();
+        nodes.sort_by_key(|n| n.pos.0);
+
+        for i in 0..nodes.len() {
+            for j in i + 1..nodes.len() {
+                if nodes[i].pos.1 == nodes[j].pos.1 && (nodes[j].pos.0 - nodes[i].pos.0) > 1 {
+                    edges.push(Edge::H {
+                        y: nodes[i].pos.1,
+                        x_range: (nodes[i].pos.0, nodes[j].pos.0),
+                    });
+                    break;
+                }
+            }
+        }
+
+        // compute vertical lines
+        nodes.sort_by_key(|n| n.pos.1);
+
+        for i in 0..nodes.len() {
+            for j in i + 1..nodes.len() {
+                if nodes[i].pos.0 == nodes[j].pos.0 && (nodes[j].pos.1 - nodes[i].pos.1) > 1 {
+                    edges.push(Edge::V {
+                        x: nodes[i].pos.0,
+                        y_range: (nodes[i].pos.1, nodes[j].pos.1),
+                    });
+                    break;
+                }
+            }
+        }
+
+        // compute diagonal lines, from each node to the nearest node down-right and up-right
+        if variant.diagonal {
+            for node in &nodes {
+                for downwards in [true, false] {
+                    let next = nodes
+                        .iter()
+                        .filter(|n| {
+                            n.pos.0 > node.pos.0 && {
+                                let dx = n.pos.0 - node.pos.0;
+                                if downwards {
+                                    n.pos.1 == node.pos.1 + dx
+                                } else {
+                                    node.pos.1 >= dx && n.pos.1 == node.pos.1 - dx
+                                }
+                            }
+                        })
+                        .min_by_key(|n| n.pos.0);
+
+                    if let Some(next) = next {
+                        if next.pos.0 - node.pos.0 > 1 {
+                            edges.push(Edge::D {
+                                from: node.pos,
+                                to: next.pos,
+                            });
+                        }
+                    }
+                }
+            }
+        }
+
+        // compute lines wrapping around the board, from the last node of each row or column to
+        // the first, unless those are the only two and already see each other directly, since a
+        // pair of islands can only be joined by as many bridges as the variant allows
+        if self.wrap {
+            let (width, height) = size;
+            let mut rows = BTreeMap::new();
+            let mut cols = BTreeMap::new();
+            for n in &nodes {
+                let (x, y) = n.pos;
+                let row = rows.entry(y).or_insert((x, x));
+                *row = (row.0.min(x), row.1.max(x));
+                let col = cols.entry(x).or_insert((y, y));
+                *col = (col.0.min(y), col.1.max(y));
+            }
+
+            for (y, (first, last)) in rows {
+                let direct = Edge::H {
+                    x_range: (first, last),
+                    y,
+                };
+                if first != last && width - last + first > 1 && !edges.contains(&direct) {
+                    edges.push(Edge::WH {
+                        x_range: (last, first),
+                        y,
+                        width,
+                    });
+                }
+            }
+            for (x, (first, last)) in cols {
+                let direct = Edge::V {
+                    x,
+                    y_range: (first, last),
+                };
+                if first != last && height - last + first > 1 && !edges.contains(&direct) {
+                    edges.push(Edge::WV {
+                        x,
+                        y_range: (last, first),
+                        height,
+                    });
+                }
+            }
+        }
+
+        edges
+    }
+
+    fn intersects(&self, a: Edge, b: Edge) -> bool {
+        a.intersects(b)
+    }
+
+    fn cells(&self, edge: Edge) -> Vec<(usize, usize)> {
+        edge.points().collect()
+    }
+
+    fn direction(&self, edge: Edge) -> (isize, isize) {
+        edge.direction()
+    }
+
+    fn wraps(&self, edge: Edge) -> bool {
+        matches!(edge, Edge::WH { .. } | Edge::WV { .. })
+    }
+
+    fn glyph(&self, edge: Edge, num_edges: NumEdges) -> char {
+        edge.as_char(num_edges)
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    fn render_text(
+        &self,
+        nodes: &[Node],
+        edges: &[Edge],
+        edge_counts: &dyn Fn(usize) -> NumEdges,
+        candidates: &dyn Fn(usize) -> bool,
+        size: (usize, usize),
+        options: RenderOptions,
+        io: &mut dyn fmt::Write,
+    ) -> fmt::Result {
+        // compute the bounds, including any lines which wrap around to the edge of the board
+        let points = nodes
+            .iter()
+            .map(|n| n.pos)
+            .chain(edges.iter().flat_map(|e| e.points()));
+        let mut max_x = points.clone().map(|p| p.0).max().unwrap_or(0) + 1;
+        let mut max_y = points.map(|p| p.1).max().unwrap_or(0) + 1;
+        if !options.compact {
+            max_x = max_x.max(size.0);
+            max_y = max_y.max(size.1);
+        }
+
+        // Collect every bridge passing through each cell first, so that the character for a
+        // cell doesn't depend on the order the edges are drawn in. Only cells with something in
+        // them are kept, by row, so that a large and mostly empty board is cheap to render.
+        let mut cells = BTreeMap::<(usize, usize), Cell>::new();
+        for (idx, edge) in edges.iter().enumerate() {
+            let ct = edge_counts(idx);
+            if ct != NumEdges::NONE {
+                for (x, y) in edge.points() {
+                    cells.entry((y, x)).or_default().add(*edge, ct);
+                }
+            } else if candidates(idx) {
+                for (x, y) in edge.points() {
+                    cells.entry((y, x)).or_default().candidate = true;
+                }
+            }
+        }
+
+        // Clues are written out in full, and any column with a clue longer than a single
+        // character, e.g. `[12]` or `3+`, is widened to fit it all the way down, so that the
+        // rows still line up. Horizontal bridges are drawn across the extra width.
+        let widths = column_widths(nodes);
+        let mut clues = BTreeMap::new();
+        for node in nodes {
+            cells.entry((node.pos.1, node.pos.0)).or_default();
+            clues.insert(node.pos, node);
+        }
+
+        // Bridges which wrap around a torus are marked just past the edge of the board they
+        // leave through, with `>` at the end of the row or `v` under the column. The bridges
+        // leaving each island to the right are noted too, to draw across the rest of its column.
+        let mut wrapped_rows = BTreeSet::new();
+        let mut wrapped_cols = BTreeSet::new();
+        let mut leaving_right = BTreeMap::new();
+        for (idx, edge) in edges.iter().enumerate() {
+            let ct = edge_counts(idx);
+            if ct == NumEdges::NONE {
+                continue;
+            }
+            match *edge {
+                Edge::WH { y, .. } => {
+                    wrapped_rows.insert(y);
+                }
+                Edge::WV { x, .. } => {
+                    wrapped_cols.insert(x);
+                }
+                _ => (),
+            }
+            if edge.direction() == (1, 0) {
+                leaving_right.insert(edge.endpoints().0, ct);
+            }
+        }
+
+        let blank = |io: &mut dyn fmt::Write, columns: core::ops::Range<usize>| {
+            for x in columns {
+                for _ in 0..width(&widths, x) {
+                    io.write_char(' ')?;
+                }
+            }
+            Ok(())
+        };
+
+        // Rows are written out one at a time, padded with spaces to the full width. In compact
+        // mode, rows with nothing in them are left empty.
+        let mut cells = cells.into_iter().peekable();
+        for y in 0..max_y {
+            let mut x = 0;
+            while let Some(((_, cx), cell)) = cells.next_if(|((cy, _), _)| *cy == y) {
+                blank(io, x..cx)?;
+                let (written, fill) = match clues.get(&(cx, y)) {
+                    Some(clue) => {
+                        let label = clue.to_string();
+                        io.write_str(&label)?;
+                        (label.chars().count(), leaving_right.get(&(cx, y)).copied())
+                    }
+                    None => {
+                        io.write_char(cell.as_char(options.style))?;
+                        (1, Some(NumEdges(cell.h)).filter(|h| *h != NumEdges::NONE))
+                    }
+                };
+                let fill = fill.map_or(' ', |ct| horizontal_char(ct, options.style));
+                for _ in written..width(&widths, cx) {
+                    io.write_char(fill)?;
+                }
+                x = cx + 1;
+            }
+            if x > 0 || !options.compact || wrapped_rows.contains(&y) {
+                blank(io, x..max_x)?;
+            }
+            if wrapped_rows.contains(&y) {
+                blank(io, max_x..size.0)?;
+                io.write_char(WRAP_MARKERS.0)?;
+            }
+            writeln!(io)?;
+        }
+        if !wrapped_cols.is_empty() {
+            let mut x = 0;
+            for cx in &wrapped_cols {
+                blank(io, x..*cx)?;
+                io.write_char(WRAP_MARKERS.1)?;
+                for _ in 1..width(&widths, *cx) {
+                    io.write_char(' ')?;
+                }
+                x = cx + 1;
+            }
+            if !options.compact {
+                blank(io, x..max_x)?;
+            }
+            writeln!(io)?;
+        }
+        Ok(())
+    }
+
+    fn render_svg(
+        &self,
+        nodes: &[Node],
+        edges: &[Edge],
+        edge_counts: &dyn Fn(usize) -> NumEdges,
+        candidates: &dyn Fn(usize) -> bool,
+        io: &mut dyn fmt::Write,
+    ) -> fmt::Result {
+        let center = |v: usize| v * SVG_CELL_SIZE + SVG_CELL_SIZE / 2;
+
+        // compute the bounds, including any lines which wrap around to the edge of the board
+        let points = nodes
+            .iter()
+            .map(|n| n.pos)
+            .chain(edges.iter().flat_map(|e| e.points()));
+        let max_x = points.clone().map(|p| p.0).max().unwrap_or(0) + 1;
+        let max_y = points.map(|p| p.1).max().unwrap_or(0) + 1;
+        let (width, height) = (max_x * SVG_CELL_SIZE, max_y * SVG_CELL_SIZE);
+
+        writeln!(
+            io,
+            r#"<svg xmlns="http://www.w3.org/2000/svg" width="{}" height="{}" viewBox="0 0 {} {}">"#,
+            width, height, width, height
+        )?;
+
+        let segments = |edge: &Edge| {
+            let ((x1, y1), (x2, y2)) = edge.endpoints();
+            let (x1, y1, x2, y2) = (center(x1), center(y1), center(x2), center(y2));
+            // Wrapping lines are drawn as two pieces running off opposite edges of the board.
+            match edge {
+                Edge::WH { .. } => vec![((x1, y1), (width, y1)), ((0, y2), (x2, y2))],
+                Edge::WV { .. } => vec![((x1, y1), (x1, height)), ((x2, 0), (x2, y2))],
+                _ => vec![((x1, y1), (x2, y2))],
+            }
+        };
+
+        // Bridges which could still be placed are drawn as faint dotted lines underneath.
+        let candidate_edges = (0..edges.len())
+            .filter(|idx| edge_counts(*idx) == NumEdges::NONE && candidates(*idx))
+            .collect::<Vec<_>>();
+        if !candidate_edges.is_empty() {
+            writeln!(
+                io,
+                r#"<g class="candidates" stroke="gray" stroke-width="1" stroke-dasharray="2 4">"#
+            )?;
+            for idx in candidate_edges {
+                for ((x1, y1), (x2, y2)) in segments(&edges[idx]) {
+                    writeln!(
+                        io,
+                        r#"<line x1="{}" y1="{}" x2="{}" y2="{}"/>"#,
+                        x1, y1, x2, y2
+                    )?;
+                }
+            }
+            writeln!(io, "</g>")?;
+        }
+
+        // Bridges are drawn first so that the islands are painted over their ends.
+        writeln!(io, r#"<g class="bridges" stroke="black" stroke-width="2">"#)?;
+        for (idx, edge) in edges.iter().enumerate() {
+            let segments = segments(edge);
+
+            // Spread multiple bridges evenly either side of the line between the islands.
+            let count = edge_counts(idx).count() as isize;
+            for i in 0..count {
+                let offset = (2 * i - (count - 1)) * SVG_BRIDGE_OFFSET as isize;
+                let (dx, dy) = match edge {
+                    Edge::H { .. } | Edge::WH { .. } => (0, offset),
+                    Edge::V { .. } | Edge::WV { .. } | Edge::D { .. } => (offset, 0),
+                };
+                for ((x1, y1), (x2, y2)) in &segments {
+                    writeln!(
+                        io,
+                        r#"<line x1="{}" y1="{}" x2="{}" y2="{}"/>"#,
+                        *x1 as isize + dx,
+                        *y1 as isize + dy,
+                        *x2 as isize + dx,
+                        *y2 as isize + dy
+                    )?;
+                }
+            }
+        }
+        writeln!(io, "</g>")?;
+
+        writeln!(io, r#"<g class="islands">"#)?;
+        for node in nodes {
+            let (cx, cy) = (center(node.pos.0), center(node.pos.1));
+            writeln!(
+                io,
+                r#"<circle cx="{}" cy="{}" r="{}" fill="white" stroke="black" stroke-width="2"/>"#,
+                cx, cy, SVG_ISLAND_RADIUS
+            )?;
+            writeln!(
+                io,
+                r#"<text x="{}" y="{}" text-anchor="middle" dominant-baseline="central" font-family="sans-serif" font-size="18">{}</text>"#,
+                cx,
+                cy,
+                node.label()
+            )?;
+        }
+        writeln!(io, "</g>")?;
+
+        writeln!(io, "</svg>")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_edge_intersections() {
+        // parallel intersections
+        assert!(Edge::V {
+            x: 0,
+            y_range: (0, 3)
+        }
+        .intersects(Edge::V {
+            x: 0,
+            y_range: (2, 4)
+        }));
+        assert!(!Edge::V {
+            x: 0,
+            y_range: (0, 2)
+        }
+        .intersects(Edge::V {
+            x: 0,
+            y_range: (2, 4)
+        }));
+        assert!(!Edge::V {
+            x: 0,
+            y_range: (2, 5)
+        }
+        .intersects(Edge::V {
+            x: 0,
+            y_range: (2, 4)
+        }));
+        assert!(Edge::H {
+            y: 0,
+            x_range: (0, 3)
+        }
+        .intersects(Edge::H {
+            y: 0,
+            x_range: (2, 4)
+        }));
+        assert!(!Edge::H {
+            y: 0,
+            x_range: (0, 2)
+        }
+        .intersects(Edge::H {
+            y: 0,
+            x_range: (2, 4)
+        }));
+        assert!(!Edge::H {
+            y: 0,
+            x_range: (2, 5)
+        }
+        .intersects(Edge::H {
+            y: 0,
+            x_range: (2, 4)
+        }));
+
+        // perpendicular intersections
+        assert!(Edge::V {
+            x: 1,
+            y_range: (0, 2)
+        }
+        .intersects(Edge::H {
+            y: 1,
+            x_range: (0, 2)
+        }));
+        assert!(!Edge::V {
+            x: 2,
+            y_range: (0, 2)
+        }
+        .intersects(Edge::H {
+            y: 1,
+            x_range: (0, 2)
+        }));
+        assert!(!Edge::V {
+            x: 1,
+            y_range: (0, 2)
+        }
+        .intersects(Edge::H {
+            y: 2,
+            x_range: (0, 2)
+        }));
+
+        // diagonal intersections, including between grid points
+        assert!(Edge::D {
+            from: (0, 0),
+            to: (2, 2)
+        }
+        .intersects(Edge::D {
+            from: (0, 3),
+            to: (3, 0)
+        }));
+        assert!(Edge::D {
+            from: (0, 0),
+            to: (2, 2)
+        }
+        .intersects(Edge::H {
+            y: 1,
+            x_range: (0, 2)
+        }));
+        assert!(!Edge::D {
+            from: (0, 0),
+            to: (2, 2)
+        }
+        .intersects(Edge::D {
+            from: (2, 2),
+            to: (4, 0)
+        }));
+    }
+}